@@ -1,5 +1,37 @@
-use std::collections::BTreeMap;
+use crate::span::Span;
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+
+// f64はEq・Ord・Hashを実装していないので、ASTに載せられるようにbit列で比較するwrapper
+#[derive(Clone, Copy, Debug)]
+pub struct FloatLiteral(pub f64);
+
+impl PartialEq for FloatLiteral {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FloatLiteral {}
+
+impl PartialOrd for FloatLiteral {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FloatLiteral {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hash for FloatLiteral {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum PrefixOprator {
@@ -22,12 +54,25 @@ pub enum InfixOprator {
     Minus,
     Slash,
     Asterisk,
+    Percent,
+    Pow,
     Gt,
     Lt,
+    GtEq,
+    LtEq,
+    // 0..10 と 0..=10
+    Range,
+    RangeInclusive,
     Assign,
+    // 複合代入。a += 1 は a = a + 1 と同じ
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
     Equal,
     Nequal,
-    Lparen, //関数呼び出しはLparenをinfixとして捉える
+    And,
+    Or,
 }
 
 impl fmt::Display for InfixOprator {
@@ -37,73 +82,167 @@ impl fmt::Display for InfixOprator {
             Self::Minus => write!(f, "-"),
             Self::Slash => write!(f, "/"),
             Self::Asterisk => write!(f, "*"),
+            Self::Percent => write!(f, "%"),
+            Self::Pow => write!(f, "**"),
             Self::Gt => write!(f, ">"),
             Self::Lt => write!(f, "<"),
+            Self::GtEq => write!(f, ">="),
+            Self::LtEq => write!(f, "<="),
+            Self::Range => write!(f, ".."),
+            Self::RangeInclusive => write!(f, "..="),
             Self::Assign => write!(f, "="),
+            Self::PlusAssign => write!(f, "+="),
+            Self::MinusAssign => write!(f, "-="),
+            Self::AsteriskAssign => write!(f, "*="),
+            Self::SlashAssign => write!(f, "/="),
             Self::Equal => write!(f, "=="),
             Self::Nequal => write!(f, "!="),
-            Self::Lparen => write!(f, "("),
+            Self::And => write!(f, "&&"),
+            Self::Or => write!(f, "||"),
         }
     }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+// どのnodeもエラーの位置を示せるようソース上の位置(span)を持つ
 pub enum Expression {
-    Identifier(String),
-    Integer(i32),
-    Bool(bool),
-    String(String),
-    Array(Vec<Expression>),
+    Identifier {
+        name: String,
+        span: Span,
+    },
+    Integer {
+        value: i64,
+        span: Span,
+    },
+    Float {
+        value: FloatLiteral,
+        span: Span,
+    },
+    Bool {
+        value: bool,
+        span: Span,
+    },
+    Null {
+        span: Span,
+    },
+    String {
+        value: String,
+        span: Span,
+    },
+    Array {
+        elements: Vec<Expression>,
+        span: Span,
+    },
     Prefix {
         operator: PrefixOprator,
         right: Box<Expression>,
+        span: Span,
     },
     Infix {
         left: Box<Expression>,
         operator: InfixOprator,
         right: Box<Expression>,
+        span: Span,
     },
     If {
         condition: Box<Expression>,
         consequence: Box<Statement>,
         alternative: Option<Box<Statement>>,
+        span: Span,
     },
     Function {
         parameters: Vec<Expression>,
         body: Box<Statement>,
+        span: Span,
     },
     Call {
         function: Box<Expression>,
         arguments: Vec<Expression>,
+        span: Span,
     },
     Index {
         left: Box<Expression>,
         index: Box<Expression>,
+        span: Span,
     },
+    // for x in xs のxがparameter
+    // for k, v in xs と書いた場合はkがparameterでvがvalue。kにはindexかmapのkeyが入る
     For {
         parameter: String,
-        array: Box<Expression>, //Expression::Array only
+        value: Option<String>,
+        iterable: Box<Expression>,
         statement: Box<Statement>,
+        span: Span,
+    },
+    While {
+        condition: Box<Expression>,
+        statement: Box<Statement>,
+        span: Span,
+    },
+    // delete a[i]。targetは常にIndex
+    Delete {
+        target: Box<Expression>,
+        span: Span,
+    },
+    // 評価したときの順序を保つため、書かれた順に並べて持つ
+    Map {
+        pairs: Vec<(Box<Expression>, Box<Expression>)>,
+        span: Span,
+    },
+    // "a${b}c" は [String("a"), b, String("c")] になる
+    Interpolated {
+        parts: Vec<Expression>,
+        span: Span,
     },
-    Map(BTreeMap<Box<Expression>, Box<Expression>>),
+}
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Identifier { span, .. }
+            | Self::Integer { span, .. }
+            | Self::Float { span, .. }
+            | Self::Bool { span, .. }
+            | Self::Null { span }
+            | Self::String { span, .. }
+            | Self::Array { span, .. }
+            | Self::Prefix { span, .. }
+            | Self::Infix { span, .. }
+            | Self::If { span, .. }
+            | Self::Function { span, .. }
+            | Self::Call { span, .. }
+            | Self::Index { span, .. }
+            | Self::For { span, .. }
+            | Self::While { span, .. }
+            | Self::Delete { span, .. }
+            | Self::Map { span, .. }
+            | Self::Interpolated { span, .. } => *span,
+        }
+    }
 }
 
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Identifier(i) => write!(f, "{}", &i),
-            Self::Integer(i) => write!(f, "{}", i),
-            Self::String(s) => write!(f, "{}", s),
-            Self::Bool(b) => write!(f, "{}", b),
-            Self::Prefix { operator, right } => write!(f, "{}{}", operator, right),
+            Self::Identifier { name, .. } => write!(f, "{}", &name),
+            Self::Integer { value, .. } => write!(f, "{}", value),
+            Self::Float { value, .. } => write!(f, "{}", value.0),
+            Self::String { value, .. } => write!(f, "{}", value),
+            Self::Bool { value, .. } => write!(f, "{}", value),
+            Self::Null { .. } => write!(f, "null"),
+            Self::Prefix {
+                operator, right, ..
+            } => write!(f, "{}{}", operator, right),
             Self::Infix {
                 left,
                 operator,
                 right,
+                ..
             } => write!(f, "({} {} {})", left, operator, right),
             Self::Call {
                 function,
                 arguments,
+                ..
             } => write!(
                 f,
                 "{}({})",
@@ -114,25 +253,53 @@ impl fmt::Display for Expression {
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
-            Self::Index { left, index } => write!(f, "{}[{}]", left, index),
+            Self::Index { left, index, .. } => write!(f, "{}[{}]", left, index),
+            Self::Interpolated { parts, .. } => {
+                for p in parts {
+                    match p {
+                        Self::String { value, .. } => write!(f, "{}", value)?,
+                        e => write!(f, "${{{}}}", e)?,
+                    }
+                }
+                Ok(())
+            }
+            Self::For {
+                parameter,
+                value: Some(value),
+                iterable,
+                statement,
+                ..
+            } => write!(
+                f,
+                "for {}, {} in {} {{ {} }}",
+                parameter, value, iterable, statement
+            ),
             Self::For {
                 parameter,
-                array,
+                iterable,
+                statement,
+                ..
+            } => write!(f, "for {} in {} {{ {} }}", parameter, iterable, statement),
+            Self::While {
+                condition,
                 statement,
-            } => write!(f, "for {} in {} {{ {} }}", parameter, array, statement),
-            Self::Array(exprs) => write!(
+                ..
+            } => write!(f, "while ({}) {{ {} }}", condition, statement),
+            Self::Delete { target, .. } => write!(f, "delete {}", target),
+            Self::Array { elements, .. } => write!(
                 f,
                 "[{}]",
-                exprs
+                elements
                     .iter()
                     .map(|e| format!("{}", e))
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
-            Self::Map(m) => write!(
+            Self::Map { pairs, .. } => write!(
                 f,
                 "{{ {} }}",
-                m.iter()
+                pairs
+                    .iter()
                     .map(|(k, v)| { format!("{}: {}", k, v) })
                     .collect::<Vec<_>>()
                     .join(", ")
@@ -145,10 +312,22 @@ impl fmt::Display for Expression {
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Statement {
     // nameが変数名で、valueが=の右辺
-    Let { name: String, value: Expression },
-    Return(Expression),
+    // docは直前の`///`コメント
+    Let {
+        name: String,
+        value: Expression,
+        doc: Option<String>,
+        span: Span,
+    },
+    Return {
+        value: Expression,
+        span: Span,
+    },
     Expression(Expression),
     Block(Vec<Statement>),
+    // ループの外で使われたときにエラーの位置を示せるようspanを持つ
+    Break(Span),
+    Continue(Span),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Hash)]
@@ -168,8 +347,10 @@ impl fmt::Display for Program {
 impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Let { name, value } => write!(f, "let {} = {};", name, value),
-            Self::Return(e) => write!(f, "return {};", e),
+            Self::Let { name, value, .. } => write!(f, "let {} = {};", name, value),
+            Self::Return { value, .. } => write!(f, "return {};", value),
+            Self::Break(_) => write!(f, "break;"),
+            Self::Continue(_) => write!(f, "continue;"),
             Self::Expression(e) => write!(f, "{}", e),
             Self::Block(stmts) => {
                 for s in stmts.iter() {
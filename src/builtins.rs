@@ -1,19 +1,32 @@
 use crate::error::Error;
-use crate::object::Object;
+use crate::object::{range_len, Object};
 use std::collections::HashMap;
 
 fn len(args: Vec<Object>) -> Result<Object, Error> {
     if args.len() != 1 {
         return Err(Error::BuiltinError {
             msg: format!("wrong number of arguments. got={}, want=1", args.len()),
+            span: None,
         });
     }
 
     match &args[0] {
-        Object::String(s) => Ok(Object::Integer(s.len() as i32)),
-        Object::Array(arr) => Ok(Object::Integer(arr.len() as i32)),
+        // バイト数ではなく文字数
+        Object::String(s) => Ok(Object::Integer(s.chars().count() as i64)),
+        Object::Array(arr) => Ok(Object::Integer(arr.len() as i64)),
+        Object::Range { start, end, .. } => match range_len(*start, *end) {
+            Some(len) => Ok(Object::Integer(len)),
+            None => Err(Error::BuiltinError {
+                msg: "integer overflow".to_string(),
+                span: None,
+            }),
+        },
         _ => Err(Error::BuiltinError {
-            msg: "`len` support String only".to_string(),
+            msg: format!(
+                "argument to `len` must be String, Array or Range, got {}",
+                args[0].type_name()
+            ),
+            span: None,
         }),
     }
 }
@@ -22,16 +35,21 @@ fn first(args: Vec<Object>) -> Result<Object, Error> {
     if args.len() != 1 {
         return Err(Error::BuiltinError {
             msg: format!("wrong number of arguments. got={}, want=1", args.len()),
+            span: None,
         });
     }
 
     match &args[0] {
-        Object::Array(arr) => match arr.get(0) {
+        Object::Array(arr) => match arr.first() {
             Some(value) => Ok(value.clone()),
             None => Ok(Object::Null),
         },
         _ => Err(Error::BuiltinError {
-            msg: format!("argument to `first` must be Array, got {:?}", &args[0]),
+            msg: format!(
+                "argument to `first` must be Array, got {}",
+                args[0].type_name()
+            ),
+            span: None,
         }),
     }
 }
@@ -40,16 +58,21 @@ fn last(args: Vec<Object>) -> Result<Object, Error> {
     if args.len() != 1 {
         return Err(Error::BuiltinError {
             msg: format!("wrong number of arguments. got={}, want=1", args.len()),
+            span: None,
         });
     }
 
     match &args[0] {
-        Object::Array(arr) => match arr.get(arr.len() - 1) {
+        Object::Array(arr) => match arr.last() {
             Some(value) => Ok(value.clone()),
             None => Ok(Object::Null),
         },
         _ => Err(Error::BuiltinError {
-            msg: format!("argument to `last` must be Array, got {:?}", &args[0]),
+            msg: format!(
+                "argument to `last` must be Array, got {}",
+                args[0].type_name()
+            ),
+            span: None,
         }),
     }
 }
@@ -58,18 +81,23 @@ fn rest(args: Vec<Object>) -> Result<Object, Error> {
     if args.len() != 1 {
         return Err(Error::BuiltinError {
             msg: format!("wrong number of arguments. got={}, want=1", args.len()),
+            span: None,
         });
     }
 
     match &args[0] {
         Object::Array(arr) => {
-            if arr.len() == 0 {
+            if arr.is_empty() {
                 return Ok(Object::Null);
             }
             Ok(Object::Array(arr[1..].to_vec()))
         }
         _ => Err(Error::BuiltinError {
-            msg: format!("argument to `rest` must be Array, got {:?}", &args[0]),
+            msg: format!(
+                "argument to `rest` must be Array, got {}",
+                args[0].type_name()
+            ),
+            span: None,
         }),
     }
 }
@@ -78,6 +106,7 @@ fn push(args: Vec<Object>) -> Result<Object, Error> {
     if args.len() != 2 {
         return Err(Error::BuiltinError {
             msg: format!("wrong number of arguments. got={}, want=2", args.len()),
+            span: None,
         });
     }
 
@@ -88,7 +117,78 @@ fn push(args: Vec<Object>) -> Result<Object, Error> {
             Ok(Object::Array(arr.to_vec()))
         }
         _ => Err(Error::BuiltinError {
-            msg: format!("first argument to `push` must be Array, got {:?}", &args[0]),
+            msg: format!(
+                "first argument to `push` must be Array, got {}",
+                args[0].type_name()
+            ),
+            span: None,
+        }),
+    }
+}
+
+// Floatは0に向かって切り捨てる
+fn int(args: Vec<Object>) -> Result<Object, Error> {
+    if args.len() != 1 {
+        return Err(Error::BuiltinError {
+            msg: format!("wrong number of arguments. got={}, want=1", args.len()),
+            span: None,
+        });
+    }
+
+    match &args[0] {
+        Object::Integer(i) => Ok(Object::Integer(*i)),
+        Object::Float(f) => {
+            let truncated = f.trunc();
+            // i64::MAXはf64で表せず2^63に丸められるので、その値自体も範囲外
+            if truncated.is_nan() || truncated < i64::MIN as f64 || truncated >= i64::MAX as f64 {
+                return Err(Error::BuiltinError {
+                    msg: format!("can not convert {} to integer", args[0]),
+                    span: None,
+                });
+            }
+            Ok(Object::Integer(truncated as i64))
+        }
+        Object::String(s) => match s.trim().parse::<i64>() {
+            Ok(i) => Ok(Object::Integer(i)),
+            Err(_) => Err(Error::BuiltinError {
+                msg: format!("can not convert \"{}\" to integer", s),
+                span: None,
+            }),
+        },
+        _ => Err(Error::BuiltinError {
+            msg: format!(
+                "argument to `int` must be Integer, Float or String, got {}",
+                args[0].type_name()
+            ),
+            span: None,
+        }),
+    }
+}
+
+fn float(args: Vec<Object>) -> Result<Object, Error> {
+    if args.len() != 1 {
+        return Err(Error::BuiltinError {
+            msg: format!("wrong number of arguments. got={}, want=1", args.len()),
+            span: None,
+        });
+    }
+
+    match &args[0] {
+        Object::Integer(i) => Ok(Object::Float(*i as f64)),
+        Object::Float(f) => Ok(Object::Float(*f)),
+        Object::String(s) => match s.trim().parse::<f64>() {
+            Ok(f) => Ok(Object::Float(f)),
+            Err(_) => Err(Error::BuiltinError {
+                msg: format!("can not convert \"{}\" to float", s),
+                span: None,
+            }),
+        },
+        _ => Err(Error::BuiltinError {
+            msg: format!(
+                "argument to `float` must be Integer, Float or String, got {}",
+                args[0].type_name()
+            ),
+            span: None,
         }),
     }
 }
@@ -108,5 +208,7 @@ pub fn new() -> HashMap<String, Object> {
     builtins.insert("rest".to_string(), Object::Builtin(rest));
     builtins.insert("push".to_string(), Object::Builtin(push));
     builtins.insert("puts".to_string(), Object::Builtin(puts));
+    builtins.insert("int".to_string(), Object::Builtin(int));
+    builtins.insert("float".to_string(), Object::Builtin(float));
     builtins
 }
@@ -0,0 +1,161 @@
+use crate::error::Error;
+use std::fmt;
+use unicode_width::UnicodeWidthChar;
+
+// エラーをrustcのようにソースの該当行と一緒に表示する
+//
+// error[E0002]: Undefined variable lenn
+//  --> script.monkey:1:1
+//   |
+// 1 | lenn("abc")
+//   | ^^^^
+//   |
+//   = help: did you mean `len`?
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    file: &'a str,
+    source: &'a str,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub fn new(error: &'a Error, file: &'a str, source: &'a str) -> Self {
+        Self {
+            error,
+            file,
+            source,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "error[{}]: {}", self.error.code(), self.error.msg())?;
+        let span = match self.error.span() {
+            Some(span) => span,
+            None => {
+                write!(f, " --> {}", self.file)?;
+                if let Some(help) = self.error.help() {
+                    write!(f, "\n = help: {}", help)?;
+                }
+                return Ok(());
+            }
+        };
+        let line = self
+            .source
+            .lines()
+            .nth(span.start.line - 1)
+            .unwrap_or_default();
+        // 行番号の桁数に合わせて左側の余白を揃える
+        let gutter = " ".repeat(span.start.line.to_string().len());
+        // 複数行にまたがるspanは1行目の終わりまでを指す
+        let end = if span.end.line == span.start.line {
+            span.end.column
+        } else {
+            line.chars().count() + 1
+        };
+        // columnは文字数なので、tabや全角文字があっても揃うよう表示幅で数える
+        // tabは端末によって幅が違うので、そのまま写す
+        let mut padding = String::new();
+        let mut width = 0;
+        for (i, c) in line.chars().enumerate() {
+            if i + 1 < span.start.column {
+                if c == '\t' {
+                    padding.push('\t');
+                } else {
+                    padding.push_str(&" ".repeat(c.width().unwrap_or(0)));
+                }
+            } else if i + 1 < end {
+                width += c.width().unwrap_or(0);
+            }
+        }
+        writeln!(f, "{}--> {}:{}", gutter, self.file, span)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", span.start.line, line)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            padding,
+            "^".repeat(std::cmp::max(1, width))
+        )?;
+        if let Some(help) = self.error.help() {
+            write!(f, "\n{} |\n{} = help: {}", gutter, gutter, help)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interpreter::Interpreter;
+
+    #[test]
+    fn test_diagnostic() {
+        let tests = vec![
+            (
+                "let a = 1;\nlenn(a)",
+                "error[E0002]: Undefined variable lenn
+ --> test:2:1
+  |
+2 | lenn(a)
+  | ^^^^
+  |
+  = help: did you mean `len`?",
+            ),
+            (
+                "let a = 1;\nlet b = a + true;",
+                "error[E0002]: Invalid infix expression
+ --> test:2:9
+  |
+2 | let b = a + true;
+  |         ^^^^^^^^",
+            ),
+            (
+                "let = 1",
+                "error[E0001]: expect `IDENT` but got `ASSIGN`
+ --> test:1:5
+  |
+1 | let = 1
+  |     ^",
+            ),
+            // tabはそのまま写し、全角文字は2桁分ずらす
+            (
+                "let 名前 = 1;\n\tlet b = 名前 + true;",
+                "error[E0002]: Invalid infix expression
+ --> test:2:10
+  |
+2 | \tlet b = 名前 + true;
+  | \t        ^^^^^^^^^^^",
+            ),
+            (
+                "let x = fn(a) {\n  a + 1\n}(true)",
+                "error[E0002]: Invalid infix expression
+ --> test:2:3
+  |
+2 |   a + 1
+  |   ^^^^^",
+            ),
+        ];
+        for (input, expect) in tests {
+            let mut interpreter = Interpreter::new();
+            match interpreter.eval_str(input) {
+                Ok(o) => panic!("expect error but got {:?}", o),
+                Err(e) => assert_eq!(format!("{}", Diagnostic::new(&e[0], "test", input)), expect),
+            }
+        }
+    }
+
+    #[test]
+    fn test_diagnostic_without_span() {
+        let e = Error::EvalError {
+            msg: "Undefined function foo".to_string(),
+            span: None,
+            help: None,
+        };
+        assert_eq!(
+            format!("{}", Diagnostic::new(&e, "test", "")),
+            "error[E0002]: Undefined function foo\n --> test"
+        );
+    }
+}
@@ -1,47 +1,305 @@
 use crate::builtins;
 use crate::object::*;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Environment {
+#[derive(Debug)]
+struct Scope {
     store: HashMap<String, Object>,
-    outer: Option<Box<Environment>>,
+    outer: Option<Environment>,
     builtins: HashMap<String, Object>,
+    // 条件にbool以外を許さないモード。一番外側のScopeの値だけを使う
+    strict: bool,
 }
 
+// Scopeを共有するためのhandle。cloneしても同じScopeを指す
+// 関数は定義時のEnvironmentをcloneして持つので、後から定義された変数(再帰呼び出しの自分自身など)や
+// 代入による変更も関数の中から見える
+// 関数が自分を含むScopeに置かれると循環参照になるので、Scopeを使い終わったらreleaseで手放す
+#[derive(Clone)]
+pub struct Environment(Rc<RefCell<Scope>>);
+
 impl Environment {
     pub fn new() -> Self {
-        Self {
+        Self(Rc::new(RefCell::new(Scope {
             store: HashMap::new(),
             outer: None,
             builtins: builtins::new(),
-        }
+            strict: false,
+        })))
     }
 
     pub fn new_enclosed(outer: &Environment) -> Self {
-        let mut env = Self::new();
-        env.outer = Some(Box::new(outer.clone()));
-        env
+        // builtinsは一番外側のScopeでだけ引く
+        Self(Rc::new(RefCell::new(Scope {
+            store: HashMap::new(),
+            outer: Some(outer.clone()),
+            builtins: HashMap::new(),
+            strict: false,
+        })))
     }
 
-    pub fn get(&self, name: &String) -> Option<&Object> {
-        match self.store.get(name) {
-            Some(v) => Some(v),
-            None => match &self.outer {
+    pub fn get(&self, name: &str) -> Option<Object> {
+        let scope = self.0.borrow();
+        match scope.store.get(name) {
+            Some(v) => Some(v.clone()),
+            None => match &scope.outer {
                 Some(o) => o.get(name),
-                None => match &self.builtins.get(name) {
-                    Some(o) => Some(o),
-                    None => None,
-                },
+                None => scope.builtins.get(name).cloned(),
             },
         }
     }
 
+    // letによる定義。常にこのScopeに追加する
     pub fn set(&mut self, name: String, value: Object) {
-        self.store.insert(name, value);
+        self.0.borrow_mut().store.insert(name, value);
+    }
+
+    // 代入。nameが定義されているScopeの値を書き換える
+    // どこにも定義されていなければこのScopeに追加する
+    pub fn assign(&mut self, name: String, value: Object) {
+        if !self.update(&name, &value) {
+            self.set(name, value);
+        }
+    }
+
+    fn update(&self, name: &String, value: &Object) -> bool {
+        let mut scope = self.0.borrow_mut();
+        if let Some(v) = scope.store.get_mut(name) {
+            *v = value.clone();
+            return true;
+        }
+        match &scope.outer {
+            Some(o) => o.update(name, value),
+            None => false,
+        }
+    }
+
+    // nameが定義されているScopeの値をその場で書き換える。定義されていなければNone
+    pub fn modify<T>(&mut self, name: &String, f: impl FnOnce(&mut Object) -> T) -> Option<T> {
+        let mut scope = self.0.borrow_mut();
+        if let Some(v) = scope.store.get_mut(name) {
+            return Some(f(v));
+        }
+        match &mut scope.outer {
+            Some(o) => o.modify(name, f),
+            None => None,
+        }
+    }
+
+    pub fn is_strict(&self) -> bool {
+        let scope = self.0.borrow();
+        match &scope.outer {
+            Some(o) => o.is_strict(),
+            None => scope.strict,
+        }
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        let mut scope = self.0.borrow_mut();
+        match &mut scope.outer {
+            Some(o) => o.set_strict(strict),
+            None => scope.strict = strict,
+        }
     }
 
     pub fn remove(&mut self, name: &String) {
-        self.store.remove(name);
+        self.0.borrow_mut().store.remove(name);
+    }
+
+    // このhandleを手放す。関数呼び出しやループの1回分のScopeを使い終わったときに呼ぶ
+    // Scopeが自分を含む関数を持っているなど、循環参照の中からしか参照されていなければ
+    // 中身を空にして循環を切り、解放されるようにする
+    pub fn release(self) {
+        // 他から参照されていなければ、このhandleを手放すだけで解放される
+        if Rc::strong_count(&self.0) == 1 {
+            return;
+        }
+        // 外側のScopeは呼び出し元がまだ使っているので、辿らずに生きているものとして扱う
+        let mut ancestors = HashSet::new();
+        let mut outer = self.0.borrow().outer.clone();
+        while let Some(o) = outer {
+            ancestors.insert(Rc::as_ptr(&o.0));
+            outer = o.0.borrow().outer.clone();
+        }
+
+        // selfから辿れるScopeを集め、その中からの参照の数を数える
+        let mut nodes = vec![self];
+        let mut index = HashMap::new();
+        index.insert(Rc::as_ptr(&nodes[0].0), 0);
+        // edges[i]はnodes[i]が参照しているScope
+        let mut edges: Vec<Vec<usize>> = vec![];
+        let mut i = 0;
+        while i < nodes.len() {
+            let mut refs = vec![];
+            nodes[i].0.borrow().references(&mut refs);
+            let mut to = vec![];
+            for r in refs {
+                let ptr = Rc::as_ptr(&r.0);
+                if ancestors.contains(&ptr) {
+                    continue;
+                }
+                match index.get(&ptr) {
+                    Some(n) => to.push(*n),
+                    None => {
+                        index.insert(ptr, nodes.len());
+                        to.push(nodes.len());
+                        nodes.push(r);
+                    }
+                }
+            }
+            edges.push(to);
+            i += 1;
+        }
+        let mut internal = vec![0; nodes.len()];
+        for to in edges.iter().flatten() {
+            internal[*to] += 1;
+        }
+
+        // 集めたもの以外から参照されているScopeと、そこから辿れるScopeはまだ使われている
+        // nodesが持つhandleの分の1を引く。selfの分は手放すので同じく引く
+        let mut alive: Vec<bool> = nodes
+            .iter()
+            .zip(&internal)
+            .map(|(n, c)| Rc::strong_count(&n.0) - 1 > *c)
+            .collect();
+        let mut stack: Vec<usize> = (0..nodes.len()).filter(|n| alive[*n]).collect();
+        while let Some(from) = stack.pop() {
+            for to in &edges[from] {
+                if !alive[*to] {
+                    alive[*to] = true;
+                    stack.push(*to);
+                }
+            }
+        }
+
+        // 残りは循環の中からしか参照されていないので、中身を空にする
+        // 中身を捨てると他のScopeも解放されうるので、borrowを終えてから捨てる
+        let mut garbage = vec![];
+        for (n, alive) in nodes.iter().zip(alive) {
+            if !alive {
+                garbage.push(std::mem::take(&mut n.0.borrow_mut().store));
+            }
+        }
+        drop(garbage);
+    }
+
+    // 参照できる名前の中からnameに一番近いものを探す。typoの候補を出すのに使う
+    pub fn similar_name(&self, name: &str) -> Option<String> {
+        let mut names = vec![];
+        let mut env = Some(self.clone());
+        while let Some(e) = env {
+            let scope = e.0.borrow();
+            names.extend(scope.store.keys().cloned());
+            names.extend(scope.builtins.keys().cloned());
+            env = scope.outer.clone();
+        }
+        // 名前が短いと何でも候補になってしまうので、許す距離は長さに合わせる
+        let limit = std::cmp::max(1, name.chars().count() / 3);
+        names
+            .into_iter()
+            .map(|n| (edit_distance(name, &n), n))
+            .filter(|(d, _)| *d <= limit)
+            .min()
+            .map(|(_, n)| n)
+    }
+}
+
+impl Scope {
+    // このScopeが参照している他のScope
+    fn references(&self, refs: &mut Vec<Environment>) {
+        for v in self.store.values() {
+            object_references(v, refs);
+        }
+        if let Some(o) = &self.outer {
+            refs.push(o.clone());
+        }
+    }
+}
+
+fn object_references(object: &Object, refs: &mut Vec<Environment>) {
+    match object {
+        Object::Function { env, .. } => refs.push(env.clone()),
+        Object::Array(elements) => {
+            for e in elements {
+                object_references(e, refs);
+            }
+        }
+        Object::Map(m) => {
+            for v in m.values() {
+                object_references(v, refs);
+            }
+        }
+        Object::Return(v) => object_references(v, refs),
+        _ => {}
+    }
+}
+
+// テストでScopeが解放されたかを確かめるためのhandle
+#[cfg(test)]
+pub(crate) struct WeakEnvironment(std::rc::Weak<RefCell<Scope>>);
+
+#[cfg(test)]
+impl WeakEnvironment {
+    pub(crate) fn is_alive(&self) -> bool {
+        self.0.upgrade().is_some()
+    }
+}
+
+#[cfg(test)]
+impl Environment {
+    pub(crate) fn downgrade(&self) -> WeakEnvironment {
+        WeakEnvironment(Rc::downgrade(&self.0))
+    }
+
+    pub(crate) fn strong_count(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 関数が自分自身を含むScopeを持つと中身の比較や表示が終わらないので、同じScopeかどうかだけを見る
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scope = self.0.borrow();
+        let mut names: Vec<&String> = scope.store.keys().collect();
+        names.sort();
+        f.debug_struct("Environment")
+            .field("names", &names)
+            .field("enclosed", &scope.outer.is_some())
+            .finish()
+    }
+}
+
+// レーベンシュタイン距離
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push(
+                *[prev[j] + cost, prev[j + 1] + 1, current[j] + 1]
+                    .iter()
+                    .min()
+                    .unwrap(),
+            );
+        }
+        prev = current;
     }
+    prev[b.len()]
 }
@@ -1,18 +1,80 @@
+use crate::span::Span;
 use std::fmt;
 
+// spanはエラーが起きたソース上の位置。埋め込みAPIから呼んだ場合など位置が無いときはNone
+// helpはエラーの直し方のヒント
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum Error {
-    ParseError { msg: String },
-    EvalError { msg: String },
-    BuiltinError { msg: String },
+    ParseError {
+        msg: String,
+        span: Option<Span>,
+    },
+    EvalError {
+        msg: String,
+        span: Option<Span>,
+        help: Option<String>,
+    },
+    BuiltinError {
+        msg: String,
+        span: Option<Span>,
+    },
+}
+
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ParseError { .. } => "E0001",
+            Self::EvalError { .. } => "E0002",
+            Self::BuiltinError { .. } => "E0003",
+        }
+    }
+
+    pub fn msg(&self) -> &str {
+        match self {
+            Self::ParseError { msg, .. }
+            | Self::EvalError { msg, .. }
+            | Self::BuiltinError { msg, .. } => msg,
+        }
+    }
+
+    pub fn help(&self) -> Option<&str> {
+        match self {
+            Self::EvalError { help, .. } => help.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::ParseError { span, .. }
+            | Self::EvalError { span, .. }
+            | Self::BuiltinError { span, .. } => *span,
+        }
+    }
+
+    // まだ位置が決まっていなければspanを設定する
+    // 内側のnodeで付けた位置を優先したいので、既にあるspanは上書きしない
+    pub fn with_span(mut self, new_span: Span) -> Self {
+        match &mut self {
+            Self::ParseError { span, .. }
+            | Self::EvalError { span, .. }
+            | Self::BuiltinError { span, .. } => {
+                if span.is_none() {
+                    *span = Some(new_span);
+                }
+            }
+        }
+        self
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::ParseError { msg } => write!(f, "ParseError: {}", msg),
-            Self::EvalError { msg } => write!(f, "EvalError: {}", msg),
-            Self::BuiltinError { msg } => write!(f, "BuiltinError: {}", msg),
+            Self::ParseError { msg, .. } => write!(f, "ParseError: {}", msg),
+            Self::EvalError { msg, .. } => write!(f, "EvalError: {}", msg),
+            Self::BuiltinError { msg, .. } => write!(f, "BuiltinError: {}", msg),
         }
     }
 }
@@ -2,126 +2,563 @@ use crate::ast;
 use crate::environment;
 use crate::error::Error;
 use crate::error::Error::EvalError;
-use crate::object::{MapKey, Object};
-use std::collections::HashMap;
+use crate::object::{range_len, MapKey, Object};
+use crate::span::Span;
+use indexmap::IndexMap;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 
-fn eval_prefix_bang_operator(right: Object) -> Result<Object, Error> {
-    match right {
-        Object::Boolean(b) => match b {
-            true => Ok(Object::Boolean(false)),
-            false => Ok(Object::Boolean(true)),
-        },
-        // NullはFalsyなのでひっくり返してtrue
-        Object::Null => Ok(Object::Boolean(true)),
-        // bool以外は全てtruthyな値として扱うので、返すのはfalse
-        _ => Ok(Object::Boolean(false)),
+// 評価を途中で打ち切る理由
+// break/continueは値ではないのでObjectには入れず、エラーと同じ経路で一番近いループまで運ぶ
+enum Interrupt {
+    Error(Error),
+    Break(Span),
+    Continue(Span),
+}
+
+impl From<Error> for Interrupt {
+    fn from(e: Error) -> Self {
+        Interrupt::Error(e)
+    }
+}
+
+impl Interrupt {
+    // ループの外まで出てきたbreak/continueはエラーにする
+    fn into_error(self) -> Error {
+        match self {
+            Interrupt::Error(e) => e,
+            Interrupt::Break(span) => EvalError {
+                msg: "`break` outside of a loop".to_string(),
+                span: Some(span),
+                help: None,
+            },
+            Interrupt::Continue(span) => EvalError {
+                msg: "`continue` outside of a loop".to_string(),
+                span: Some(span),
+                help: None,
+            },
+        }
+    }
+}
+
+// if, while, !, &&, ||で条件として使う値をboolにする
+// 普段はObject::is_truthyに従い、strictモードではbool以外をエラーにする
+// whatはエラーメッセージに使う、何の条件かの説明
+fn eval_condition(condition: Object, strict: bool, what: &str) -> Result<bool, Error> {
+    match condition {
+        Object::Boolean(b) => Ok(b),
+        c if strict => Err(EvalError {
+            msg: format!("{} must be boolean, but got {}", what, c.type_name()),
+            span: None,
+            help: None,
+        }),
+        c => Ok(c.is_truthy()),
     }
 }
 
+fn eval_prefix_bang_operator(right: Object, strict: bool) -> Result<Object, Error> {
+    let b = eval_condition(right, strict, "operand of `!`")?;
+    Ok(Object::Boolean(!b))
+}
+
 fn eval_prefix_minus_operator(right: Object) -> Result<Object, Error> {
     match right {
-        Object::Integer(i) => Ok(Object::Integer(-i)),
+        // -i64::MINはi64に収まらない
+        Object::Integer(i) => checked_integer(i.checked_neg()),
+        Object::Float(f) => Ok(Object::Float(-f)),
         _ => Err(EvalError {
             msg: "Invalid prefix expression".to_string(),
+            span: None,
+            help: None,
         }),
     }
 }
 
-fn eval_prefix_expression(op: ast::PrefixOprator, right: Object) -> Result<Object, Error> {
+fn eval_prefix_expression(
+    op: ast::PrefixOprator,
+    right: Object,
+    strict: bool,
+) -> Result<Object, Error> {
     match op {
-        ast::PrefixOprator::Bang => eval_prefix_bang_operator(right),
+        ast::PrefixOprator::Bang => eval_prefix_bang_operator(right, strict),
         ast::PrefixOprator::Minus => eval_prefix_minus_operator(right),
     }
 }
 
+// 整数演算の結果がi64に収まらなければエラーにする
+fn checked_integer(result: Option<i64>) -> Result<Object, Error> {
+    match result {
+        Some(i) => Ok(Object::Integer(i)),
+        None => Err(EvalError {
+            msg: "integer overflow".to_string(),
+            span: None,
+            help: None,
+        }),
+    }
+}
+
+// 繰り返し二乗法で計算する。指数がu32に収まらなくても 1 ** 5000000000 のように結果が小さければ計算できる
+fn checked_pow(mut base: i64, mut exp: i64) -> Option<i64> {
+    let mut result: i64 = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.checked_mul(base)?;
+        }
+        exp >>= 1;
+        // 残りの指数がある時だけ二乗する。最後の二乗のoverflowは結果に影響しない
+        if exp > 0 {
+            base = base.checked_mul(base)?;
+        }
+    }
+    Some(result)
+}
+
+fn eval_float_infix_expression(op: ast::InfixOprator, l: f64, r: f64) -> Result<Object, Error> {
+    match op {
+        ast::InfixOprator::Plus => Ok(Object::Float(l + r)),
+        ast::InfixOprator::Minus => Ok(Object::Float(l - r)),
+        ast::InfixOprator::Asterisk => Ok(Object::Float(l * r)),
+        ast::InfixOprator::Slash => Ok(Object::Float(l / r)),
+        ast::InfixOprator::Percent => Ok(Object::Float(l % r)),
+        ast::InfixOprator::Pow => Ok(Object::Float(l.powf(r))),
+        ast::InfixOprator::Gt => Ok(Object::Boolean(l > r)),
+        ast::InfixOprator::Lt => Ok(Object::Boolean(l < r)),
+        ast::InfixOprator::GtEq => Ok(Object::Boolean(l >= r)),
+        ast::InfixOprator::LtEq => Ok(Object::Boolean(l <= r)),
+        ast::InfixOprator::Equal => Ok(Object::Boolean(l == r)),
+        ast::InfixOprator::Nequal => Ok(Object::Boolean(l != r)),
+        ast::InfixOprator::Range | ast::InfixOprator::RangeInclusive => Err(EvalError {
+            msg: "range bounds must be integer".to_string(),
+            span: None,
+            help: None,
+        }),
+        o => Err(EvalError {
+            msg: format!("unsupported operator `{}` for float", o),
+            span: None,
+            help: None,
+        }),
+    }
+}
+
 fn eval_infix_expression(
     op: ast::InfixOprator,
     left: Object,
     right: Object,
 ) -> Result<Object, Error> {
+    // 片方でもFloatならもう片方もFloatに揃えて計算する
+    match (&left, &right) {
+        (Object::Float(l), Object::Float(r)) => return eval_float_infix_expression(op, *l, *r),
+        (Object::Integer(l), Object::Float(r)) => {
+            return eval_float_infix_expression(op, *l as f64, *r)
+        }
+        (Object::Float(l), Object::Integer(r)) => {
+            return eval_float_infix_expression(op, *l, *r as f64)
+        }
+        _ => {}
+    }
     match op {
         ast::InfixOprator::Plus => match (left, right) {
-            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Integer(l + r)),
+            (Object::Integer(l), Object::Integer(r)) => checked_integer(l.checked_add(r)),
             (Object::String(l), Object::String(r)) => Ok(Object::String(l + &r)),
             _ => Err(EvalError {
                 msg: "Invalid infix expression".to_string(),
+                span: None,
+                help: None,
             }),
         },
         ast::InfixOprator::Minus => match (left, right) {
-            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Integer(l - r)),
+            (Object::Integer(l), Object::Integer(r)) => checked_integer(l.checked_sub(r)),
             _ => Err(EvalError {
                 msg: "Invalid infix expression".to_string(),
+                span: None,
+                help: None,
             }),
         },
         ast::InfixOprator::Asterisk => match (left, right) {
-            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Integer(l * r)),
+            (Object::Integer(l), Object::Integer(r)) => checked_integer(l.checked_mul(r)),
             _ => Err(EvalError {
                 msg: "Invalid infix expression".to_string(),
+                span: None,
+                help: None,
             }),
         },
         ast::InfixOprator::Slash => match (left, right) {
-            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Integer(l / r)),
+            (Object::Integer(_), Object::Integer(0)) => Err(EvalError {
+                msg: "division by zero".to_string(),
+                span: None,
+                help: None,
+            }),
+            // i64::MIN / -1 はoverflowになる
+            (Object::Integer(l), Object::Integer(r)) => checked_integer(l.checked_div(r)),
             _ => Err(EvalError {
                 msg: "Invalid infix expression".to_string(),
+                span: None,
+                help: None,
             }),
         },
-        ast::InfixOprator::Gt => match (left, right) {
-            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Boolean(l > r)),
+        ast::InfixOprator::Percent => match (left, right) {
+            (Object::Integer(_), Object::Integer(0)) => Err(EvalError {
+                msg: "modulo by zero".to_string(),
+                span: None,
+                help: None,
+            }),
+            // i64::MIN % -1 は数学的には0なのでoverflowにはしない
+            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Integer(l.wrapping_rem(r))),
             _ => Err(EvalError {
                 msg: "Invalid infix expression".to_string(),
+                span: None,
+                help: None,
             }),
         },
-        ast::InfixOprator::Lt => match (left, right) {
-            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Boolean(l < r)),
+        ast::InfixOprator::Pow => match (left, right) {
+            // 負の指数は整数にならないのでFloatで計算する
+            (Object::Integer(l), Object::Integer(r)) if r < 0 => {
+                Ok(Object::Float((l as f64).powf(r as f64)))
+            }
+            (Object::Integer(l), Object::Integer(r)) => checked_integer(checked_pow(l, r)),
             _ => Err(EvalError {
                 msg: "Invalid infix expression".to_string(),
+                span: None,
+                help: None,
             }),
         },
-        ast::InfixOprator::Equal => match (left, right) {
-            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Boolean(l == r)),
-            (Object::Boolean(l), Object::Boolean(r)) => Ok(Object::Boolean(l == r)),
-            (Object::String(l), Object::String(r)) => Ok(Object::Boolean(l == r)),
+        ast::InfixOprator::Range => match (left, right) {
+            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Range {
+                start: l,
+                end: r,
+                inclusive: false,
+            }),
             _ => Err(EvalError {
-                msg: "Invalid infix expression".to_string(),
+                msg: "range bounds must be integer".to_string(),
+                span: None,
+                help: None,
             }),
         },
-        ast::InfixOprator::Nequal => match (left, right) {
-            (Object::Integer(l), Object::Integer(r)) => Ok(Object::Boolean(l != r)),
-            (Object::Boolean(l), Object::Boolean(r)) => Ok(Object::Boolean(l != r)),
-            (Object::String(l), Object::String(r)) => Ok(Object::Boolean(l != r)),
+        // 終わりを含むrangeは終わりを1つ後ろにずらして持つ
+        ast::InfixOprator::RangeInclusive => match (left, right) {
+            (Object::Integer(l), Object::Integer(r)) => match r.checked_add(1) {
+                Some(end) => Ok(Object::Range {
+                    start: l,
+                    end,
+                    inclusive: true,
+                }),
+                None => checked_integer(None),
+            },
             _ => Err(EvalError {
+                msg: "range bounds must be integer".to_string(),
+                span: None,
+                help: None,
+            }),
+        },
+        ast::InfixOprator::GtEq
+        | ast::InfixOprator::LtEq
+        | ast::InfixOprator::Gt
+        | ast::InfixOprator::Lt => match left.compare(&right) {
+            Some(ordering) => Ok(Object::Boolean(match op {
+                ast::InfixOprator::GtEq => ordering != Ordering::Less,
+                ast::InfixOprator::LtEq => ordering != Ordering::Greater,
+                ast::InfixOprator::Gt => ordering == Ordering::Greater,
+                _ => ordering == Ordering::Less,
+            })),
+            None => Err(EvalError {
                 msg: "Invalid infix expression".to_string(),
+                span: None,
+                help: None,
             }),
         },
-        o => panic!("eval infix expression for {:?} is not implemented yet.", o),
+        // 型が違う場合もエラーにはせず、等しくないとみなす
+        ast::InfixOprator::Equal => Ok(Object::Boolean(left.structural_eq(&right))),
+        ast::InfixOprator::Nequal => Ok(Object::Boolean(!left.structural_eq(&right))),
+        o => Err(EvalError {
+            msg: format!("unsupported operator `{}`", o),
+            span: None,
+            help: None,
+        }),
+    }
+}
+
+// 複合代入の演算部分。ただの代入ならNone
+fn compound_operator(operator: &ast::InfixOprator) -> Option<ast::InfixOprator> {
+    match operator {
+        ast::InfixOprator::PlusAssign => Some(ast::InfixOprator::Plus),
+        ast::InfixOprator::MinusAssign => Some(ast::InfixOprator::Minus),
+        ast::InfixOprator::AsteriskAssign => Some(ast::InfixOprator::Asterisk),
+        ast::InfixOprator::SlashAssign => Some(ast::InfixOprator::Slash),
+        _ => None,
+    }
+}
+
+// a[i][j] = v のような要素への代入
+// 一番外側の変数が持つ値をその場で書き換えるので、配列や連想配列を作り直さない
+fn eval_index_assign(
+    target: &ast::Expression,
+    operator: &ast::InfixOprator,
+    right: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let (root, name, indexes) = match split_index_target(target) {
+        Some(t) => t,
+        None => {
+            return Err(EvalError {
+                msg: format!("can not assign {} to {}", right, target),
+                span: None,
+                help: None,
+            }
+            .into())
+        }
+    };
+    // 書き換えの途中でenvを使わないよう、先に全て評価しておく
+    let indexes = eval_expressions(indexes, env)?;
+    let value = eval_expression(right, env)?;
+    let op = compound_operator(operator);
+    modify_variable(root, name, "index assignment", env, |current| {
+        set_index(current, indexes, op, value)
+    })
+}
+
+// delete a[i] のような要素の削除。取り除いた値を返す
+fn eval_delete(
+    target: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let (root, name, indexes) = match split_index_target(target) {
+        Some(t) => t,
+        None => {
+            return Err(EvalError {
+                msg: format!("can not delete {}", target),
+                span: None,
+                help: None,
+            }
+            .into())
+        }
+    };
+    let mut indexes = eval_expressions(indexes, env)?;
+    let last = indexes.pop().unwrap();
+    modify_variable(root, name, "delete", env, |current| {
+        let mut slot = current;
+        for index in indexes {
+            slot = index_slot(slot, index, false, "delete")?;
+        }
+        remove_index(slot, last)
+    })
+}
+
+// a[i][j] を、一番外側の変数(とその名前)と外側から順に並べたindexに分ける
+// 変数から始まっていなければNone
+fn split_index_target(
+    target: &ast::Expression,
+) -> Option<(&ast::Expression, &String, Vec<&ast::Expression>)> {
+    let mut indexes = vec![];
+    let mut root = target;
+    while let ast::Expression::Index { left, index, .. } = root {
+        indexes.push(index.as_ref());
+        root = left;
+    }
+    indexes.reverse();
+    match root {
+        ast::Expression::Identifier { name, .. } if !indexes.is_empty() => {
+            Some((root, name, indexes))
+        }
+        _ => None,
+    }
+}
+
+// 変数が持つ値をその場で書き換える
+// 一番外側の変数の値を書き換えるので、配列や連想配列を作り直さない
+fn modify_variable(
+    root: &ast::Expression,
+    name: &String,
+    what: &str,
+    env: &mut environment::Environment,
+    f: impl FnOnce(&mut Object) -> Result<Object, Error>,
+) -> Result<Object, Interrupt> {
+    match env.modify(name, f) {
+        Some(result) => Ok(result?),
+        // builtinは書き換えられない。未定義ならその旨のエラーになる
+        None => {
+            let o = eval_expression(root, env)?;
+            Err(EvalError {
+                msg: format!("{} does not support {}", o.type_name(), what),
+                span: None,
+                help: None,
+            }
+            .into())
+        }
+    }
+}
+
+fn set_index(
+    target: &mut Object,
+    indexes: Vec<Object>,
+    op: Option<ast::InfixOprator>,
+    value: Object,
+) -> Result<Object, Error> {
+    let mut slot = target;
+    let last = indexes.len() - 1;
+    for (n, index) in indexes.into_iter().enumerate() {
+        // 連想配列に無いkeyは、最後のindexへの単純な代入のときだけ追加する
+        let insert = n == last && op.is_none();
+        slot = index_slot(slot, index, insert, "index assignment")?;
+    }
+    let value = match op {
+        Some(op) => eval_infix_expression(op, slot.clone(), value)?,
+        None => value,
+    };
+    *slot = value.clone();
+    Ok(value)
+}
+
+fn index_slot<'a>(
+    target: &'a mut Object,
+    index: Object,
+    insert: bool,
+    what: &str,
+) -> Result<&'a mut Object, Error> {
+    let type_name = target.type_name();
+    match (target, index) {
+        (Object::Array(arr), Object::Integer(i)) => {
+            let len = arr.len();
+            match usize::try_from(i).ok().and_then(move |i| arr.get_mut(i)) {
+                Some(value) => Ok(value),
+                None => Err(out_of_range(i, len)),
+            }
+        }
+        (Object::Map(m), key) => {
+            let key = MapKey::try_from(key)?;
+            if insert {
+                return Ok(m.entry(key).or_insert_with(|| Box::new(Object::Null)));
+            }
+            match m.get_mut(&key) {
+                Some(value) => Ok(value),
+                None => Err(EvalError {
+                    msg: format!("key {} is not found in map", key),
+                    span: None,
+                    help: None,
+                }),
+            }
+        }
+        (Object::Array(_), i) => Err(EvalError {
+            msg: format!("Can not index array with {}", i.type_name()),
+            span: None,
+            help: None,
+        }),
+        _ => Err(EvalError {
+            msg: format!("{} does not support {}", type_name, what),
+            span: None,
+            help: None,
+        }),
+    }
+}
+
+// 配列はindexの要素を詰めて取り除く。連想配列は無いkeyならnullを返す
+// 連想配列の残りのkeyの順序は変えない
+fn remove_index(target: &mut Object, index: Object) -> Result<Object, Error> {
+    match (target, index) {
+        (Object::Array(arr), Object::Integer(i)) => match usize::try_from(i) {
+            Ok(n) if n < arr.len() => Ok(arr.remove(n)),
+            _ => Err(out_of_range(i, arr.len())),
+        },
+        (Object::Map(m), key) => {
+            let key = MapKey::try_from(key)?;
+            Ok(m.shift_remove(&key).map_or(Object::Null, |v| *v))
+        }
+        (Object::Array(_), i) => Err(EvalError {
+            msg: format!("Can not index array with {}", i.type_name()),
+            span: None,
+            help: None,
+        }),
+        (o, _) => Err(EvalError {
+            msg: format!("{} does not support delete", o.type_name()),
+            span: None,
+            help: None,
+        }),
+    }
+}
+
+fn out_of_range(i: i64, len: usize) -> Error {
+    EvalError {
+        msg: format!("index {} is out of range for length {}", i, len),
+        span: None,
+        help: None,
     }
 }
 
+// && と || のoperandを条件として評価する
+fn eval_logical_operand(
+    operator: &ast::InfixOprator,
+    operand: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<bool, Interrupt> {
+    let operand = eval_expression(operand, env)?;
+    let what = format!("operand of `{}`", operator);
+    Ok(eval_condition(operand, env.is_strict(), &what)?)
+}
+
 // parametersをkeyとしてargsで渡されたObjectをenv登録
 fn extend_function_env(
-    parameters: Vec<ast::Expression>,
+    parameters: &[ast::Expression],
     env: &environment::Environment,
     args: Vec<Object>,
 ) -> environment::Environment {
     let mut env = environment::Environment::new_enclosed(env);
     for (p, arg) in parameters.iter().zip(args) {
-        if let ast::Expression::Identifier(i) = p {
-            env.set(i.to_string(), arg)
+        if let ast::Expression::Identifier { name, .. } = p {
+            env.set(name.to_string(), arg)
         }
     }
     env
 }
 
-fn apply_function(function: Object, args: Vec<Object>) -> Result<Object, Error> {
+// 関数呼び出しのネストの上限。これを超えるとRustのスタックを使い切る前にエラーにする
+const MAX_CALL_DEPTH: usize = 100;
+
+thread_local! {
+    static CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// 関数呼び出しの深さを数える。エラーで抜けた時もDropで戻す
+struct CallDepthGuard;
+
+impl CallDepthGuard {
+    fn enter() -> Result<CallDepthGuard, Error> {
+        CALL_DEPTH.with(|depth| {
+            if depth.get() >= MAX_CALL_DEPTH {
+                return Err(EvalError {
+                    msg: "maximum recursion depth exceeded".to_string(),
+                    span: None,
+                    help: Some(format!(
+                        "function calls can be nested up to {} levels",
+                        MAX_CALL_DEPTH
+                    )),
+                });
+            }
+            depth.set(depth.get() + 1);
+            Ok(CallDepthGuard)
+        })
+    }
+}
+
+impl Drop for CallDepthGuard {
+    fn drop(&mut self) {
+        CALL_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+pub(crate) fn apply_function(function: Object, args: Vec<Object>) -> Result<Object, Error> {
     match function {
         Object::Function {
             parameters,
             body,
             env,
         } => {
+            let _guard = CallDepthGuard::enter()?;
             // parametersとargsの対応付け。関数の引数にあるparamsにargsのobjを対応させる
-            let mut extended_env = extend_function_env(parameters, &env, args);
-            let evaluated = eval_statement(body.as_ref().clone(), &mut extended_env)?;
+            let mut extended_env = extend_function_env(&parameters, &env, args);
+            let evaluated = eval_statement(&body, &mut extended_env);
+            extended_env.release();
+            // 関数の外のループはbreakできない
+            let evaluated = evaluated.map_err(Interrupt::into_error)?;
             match evaluated {
                 Object::Return(o) => Ok(o.as_ref().clone()),
                 _ => Ok(evaluated),
@@ -132,15 +569,17 @@ fn apply_function(function: Object, args: Vec<Object>) -> Result<Object, Error>
             Err(e) => Err(e),
         },
         _ => Err(EvalError {
-            msg: format!("{:?} Can not be called", function),
+            msg: format!("{} can not be called", function.type_name()),
+            span: None,
+            help: None,
         }),
     }
 }
 
-fn eval_expressions(
-    expressions: Vec<ast::Expression>,
+fn eval_expressions<'a>(
+    expressions: impl IntoIterator<Item = &'a ast::Expression>,
     env: &mut environment::Environment,
-) -> Result<Vec<Object>, Error> {
+) -> Result<Vec<Object>, Interrupt> {
     let mut result = vec![];
     for e in expressions {
         let evaluated = eval_expression(e, env)?;
@@ -149,145 +588,415 @@ fn eval_expressions(
     Ok(result)
 }
 
+// 長さlenのものをstart..endで切り出せるか確かめる
+fn slice_bounds(len: i64, start: i64, end: i64) -> Result<(i64, i64), Error> {
+    if 0 <= start && start <= end && end <= len {
+        Ok((start, end))
+    } else {
+        Err(EvalError {
+            msg: format!(
+                "slice {}..{} is out of range for length {}",
+                start, end, len
+            ),
+            span: None,
+            help: None,
+        })
+    }
+}
+
 fn eval_index_expression(left: Object, index: Object) -> Result<Object, Error> {
     match (left, index) {
+        // rangeで切り出す
+        (Object::Array(arr), Object::Range { start, end, .. }) => {
+            let (start, end) = slice_bounds(arr.len() as i64, start, end)?;
+            Ok(Object::Array(arr[start as usize..end as usize].to_vec()))
+        }
+        (
+            Object::Range { start, end, .. },
+            Object::Range {
+                start: s, end: e, ..
+            },
+        ) => {
+            let len = range_len(start, end).unwrap_or(i64::MAX);
+            let (s, e) = slice_bounds(len, s, e)?;
+            Ok(Object::Range {
+                start: start + s,
+                end: start + e,
+                inclusive: false,
+            })
+        }
+        // 文字列は文字単位で扱う
+        (Object::String(s), Object::Integer(i)) => match usize::try_from(i) {
+            Ok(i) => match s.chars().nth(i) {
+                Some(c) => Ok(Object::String(c.to_string())),
+                None => Ok(Object::Null),
+            },
+            Err(_) => Ok(Object::Null),
+        },
+        (Object::String(s), Object::Range { start, end, .. }) => {
+            let (start, end) = slice_bounds(s.chars().count() as i64, start, end)?;
+            Ok(Object::String(
+                s.chars()
+                    .skip(start as usize)
+                    .take((end - start) as usize)
+                    .collect(),
+            ))
+        }
+        // 配列と同じく範囲外はnull
+        (Object::Range { start, end, .. }, Object::Integer(i)) => {
+            let len = range_len(start, end).unwrap_or(i64::MAX);
+            if 0 <= i && i < len {
+                Ok(Object::Integer(start + i))
+            } else {
+                Ok(Object::Null)
+            }
+        }
         // arrayとintegerのときのみ解決する
         (Object::Array(arr), Object::Integer(i)) => match arr.get(i as usize) {
             Some(value) => Ok(value.clone()),
             None => Ok(Object::Null),
         },
-        (Object::Map(m), obj) => match m.get(&MapKey::from(obj)) {
+        (Object::Map(m), obj) => match m.get(&MapKey::try_from(obj)?) {
             Some(value) => Ok(value.as_ref().clone()),
             None => Ok(Object::Null),
         },
         (l, i) => Err(EvalError {
-            msg: format!("Can not resolve {:?} and {:?}", l, i),
+            msg: format!("Can not index {} with {}", l.type_name(), i.type_name()),
+            span: None,
+            help: None,
+        }),
+    }
+}
+
+// forで繰り返す(indexかkey, 値)の組を順に返す
+fn eval_iterable(iterable: Object) -> Result<Box<dyn Iterator<Item = (Object, Object)>>, Error> {
+    match iterable {
+        Object::Array(arr) => Ok(Box::new(
+            arr.into_iter()
+                .enumerate()
+                .map(|(i, o)| (Object::Integer(i as i64), o)),
+        )),
+        // 文字列は1文字ずつ
+        Object::String(s) => Ok(Box::new(
+            s.chars()
+                .collect::<Vec<_>>()
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| (Object::Integer(i as i64), Object::String(c.to_string()))),
+        )),
+        // 要素は1つずつ作る
+        Object::Range { start, end, .. } => {
+            Ok(Box::new((start..end).enumerate().map(|(i, n)| {
+                (Object::Integer(i as i64), Object::Integer(n))
+            })))
+        }
+        // 追加した順に繰り返す
+        Object::Map(m) => Ok(Box::new(m.into_iter().map(|(k, v)| (Object::from(k), *v)))),
+        o => Err(EvalError {
+            msg: format!("{} is not iterable", o.type_name()),
+            span: None,
+            help: None,
         }),
     }
 }
 
 fn eval_expression(
-    expression: ast::Expression,
+    expression: &ast::Expression,
     env: &mut environment::Environment,
-) -> Result<Object, Error> {
+) -> Result<Object, Interrupt> {
+    // エラーにはそれを起こした一番内側のnodeの位置を付ける
+    eval_expression_node(expression, env).map_err(|i| match i {
+        Interrupt::Error(e) => Interrupt::Error(e.with_span(expression.span())),
+        i => i,
+    })
+}
+
+fn eval_expression_node(
+    expression: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    // 再帰の度にこの関数のフレームが積まれるので、大きな分岐はそれぞれ別の関数に分けて
+    // フレームを小さく保つ
     match expression {
-        ast::Expression::Integer(i) => Ok(Object::Integer(i)),
-        ast::Expression::Bool(b) => Ok(Object::Boolean(b)),
-        ast::Expression::String(s) => Ok(Object::String(s)),
-        ast::Expression::Prefix { operator, right } => {
-            let right = eval_expression(right.as_ref().clone(), env)?;
-            eval_prefix_expression(operator, right)
-        }
+        ast::Expression::Integer { value, .. } => Ok(Object::Integer(*value)),
+        ast::Expression::Float { value, .. } => Ok(Object::Float(value.0)),
+        ast::Expression::Bool { value, .. } => Ok(Object::Boolean(*value)),
+        ast::Expression::Null { .. } => Ok(Object::Null),
+        ast::Expression::String { value, .. } => Ok(Object::String(value.clone())),
+        ast::Expression::Interpolated { parts, .. } => eval_interpolated(parts, env),
+        ast::Expression::Prefix {
+            operator, right, ..
+        } => eval_prefix(operator, right, env),
         ast::Expression::Infix {
             left,
             operator,
             right,
-        } => match operator {
-            ast::InfixOprator::Assign => match *left {
-                // Assignのoperatorの時だけ分岐を分ける
-                // eval_infixだと既にObjectになってしまっていて、
-                // Identifierのnameが取れないため
-                ast::Expression::Identifier(i) => {
-                    let right = eval_expression(right.as_ref().clone(), env)?;
-                    env.set(i, right.clone());
-                    Ok(right)
-                }
-                _ => Err(Error::EvalError {
-                    msg: format!("can not assign {} to {}", right, left),
-                }),
-            },
-            _ => {
-                let left = eval_expression(left.as_ref().clone(), env)?;
-                let right = eval_expression(right.as_ref().clone(), env)?;
-                eval_infix_expression(operator, left, right)
-            }
-        },
+            ..
+        } => eval_infix(left, operator, right, env),
         ast::Expression::If {
             condition,
             consequence,
             alternative,
-        } => match eval_expression(condition.as_ref().clone(), env)? {
-            Object::Boolean(b) => {
-                if b {
-                    eval_statement(consequence.as_ref().clone(), env)
-                } else {
-                    match alternative {
-                        Some(a) => eval_statement(a.as_ref().clone(), env),
-                        None => Ok(Object::Null),
-                    }
-                }
-            }
-            c => {
-                return Err(EvalError {
-                    msg: format!("If condition must be boolean, but got {:?}", c),
-                })
-            }
-        },
-        ast::Expression::Identifier(name) => {
-            if let Some(o) = env.get(&name) {
-                return Ok(o.clone());
-            } else {
-                Err(EvalError {
-                    msg: format!("Undefined variable {}", name),
-                })
-            }
-        }
+            ..
+        } => eval_if(condition, consequence, alternative.as_deref(), env),
+        ast::Expression::Identifier { name, .. } => eval_identifier(name, env),
         ast::Expression::Call {
             function,
             arguments,
-        } => {
-            let args = eval_expressions(arguments, env)?;
-            let function = eval_expression(function.as_ref().clone(), env)?;
-            apply_function(function, args)
-        }
-        ast::Expression::Function { parameters, body } => Ok(Object::Function {
-            parameters,
-            body,
+            ..
+        } => eval_call(function, arguments, env),
+        ast::Expression::Function {
+            parameters, body, ..
+        } => Ok(Object::Function {
+            parameters: parameters.clone(),
+            body: body.clone(),
             env: env.clone(),
         }),
-        ast::Expression::Array(arr) => {
-            let elements = eval_expressions(arr, env)?;
-            Ok(Object::Array(elements))
-        }
-        ast::Expression::Index { left, index } => {
-            let l = eval_expression(left.as_ref().clone(), env)?;
-            let i = eval_expression(index.as_ref().clone(), env)?;
-            eval_index_expression(l, i)
-        }
-        ast::Expression::Map(m) => {
-            let mut map = HashMap::new();
-            for (k, v) in m.iter() {
-                let key = eval_expression(k.as_ref().clone(), env)?;
-                let value = eval_expression(v.as_ref().clone(), env)?;
-                map.insert(MapKey::from(key), Box::new(value));
-            }
-            Ok(Object::Map(map))
+        ast::Expression::Array { elements, .. } => {
+            eval_expressions(elements, env).map(Object::Array)
         }
+        ast::Expression::Index { left, index, .. } => eval_index(left, index, env),
+        ast::Expression::Map { pairs, .. } => eval_map_literal(pairs, env),
         ast::Expression::For {
             parameter,
-            array,
+            value,
+            iterable,
             statement,
-        } => {
-            let mut result = Object::Null;
-            // arrayの値を一つずつenv上のparameterにマッピング
-            if let ast::Expression::Array(array) = *array.clone() {
-                for object in eval_expressions(array, env)? {
-                    env.set(parameter.clone(), object);
-                    if let ast::Statement::Block(stmts) = *statement.clone() {
-                        result = eval_block_statements(stmts, env)?;
-                    }
-                }
-                env.remove(&parameter.clone());
+            ..
+        } => eval_for(parameter, value.as_ref(), iterable, statement, env),
+        ast::Expression::Delete { target, .. } => eval_delete(target, env),
+        ast::Expression::While {
+            condition,
+            statement,
+            ..
+        } => eval_while(condition, statement, env),
+        //_ => Err(EvalError { msg: "not implemented yet".to_string(), }),
+    }
+}
+
+fn eval_prefix(
+    operator: &ast::PrefixOprator,
+    right: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let right = eval_expression(right, env)?;
+    Ok(eval_prefix_expression(
+        operator.clone(),
+        right,
+        env.is_strict(),
+    )?)
+}
+
+fn eval_index(
+    left: &ast::Expression,
+    index: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let l = eval_expression(left, env)?;
+    let i = eval_expression(index, env)?;
+    Ok(eval_index_expression(l, i)?)
+}
+
+// 各部分をDisplayで文字列にしてつなげる
+fn eval_interpolated(
+    parts: &[ast::Expression],
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let mut s = String::new();
+    for p in parts {
+        s.push_str(&eval_expression(p, env)?.to_string());
+    }
+    Ok(Object::String(s))
+}
+
+fn eval_infix(
+    left: &ast::Expression,
+    operator: &ast::InfixOprator,
+    right: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    match operator {
+        ast::InfixOprator::Assign
+        | ast::InfixOprator::PlusAssign
+        | ast::InfixOprator::MinusAssign
+        | ast::InfixOprator::AsteriskAssign
+        | ast::InfixOprator::SlashAssign => eval_assign(left, operator, right, env),
+        ast::InfixOprator::And | ast::InfixOprator::Or => eval_logical(left, operator, right, env),
+        _ => {
+            let left = eval_expression(left, env)?;
+            let right = eval_expression(right, env)?;
+            Ok(eval_infix_expression(operator.clone(), left, right)?)
+        }
+    }
+}
+
+// 左辺だけで結果が決まる場合は右辺を評価しない
+fn eval_logical(
+    left: &ast::Expression,
+    operator: &ast::InfixOprator,
+    right: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let left = eval_logical_operand(operator, left, env)?;
+    if (*operator == ast::InfixOprator::And) != left {
+        return Ok(Object::Boolean(left));
+    }
+    let right = eval_logical_operand(operator, right, env)?;
+    Ok(Object::Boolean(right))
+}
+
+fn eval_assign(
+    left: &ast::Expression,
+    operator: &ast::InfixOprator,
+    right: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    match left {
+        // Assignのoperatorの時だけ分岐を分ける
+        // eval_infixだと既にObjectになってしまっていて、
+        // Identifierのnameが取れないため
+        ast::Expression::Identifier { name, .. } => {
+            let mut right = eval_expression(right, env)?;
+            if let Some(op) = compound_operator(operator) {
+                let current = eval_expression(left, env)?;
+                right = eval_infix_expression(op, current, right)?;
+            }
+            // 関数の外で定義された変数への代入も、その変数自体を書き換える
+            env.assign(name.clone(), right.clone());
+            Ok(right)
+        }
+        ast::Expression::Index { .. } => eval_index_assign(left, operator, right, env),
+        _ => Err(EvalError {
+            msg: format!("can not assign {} to {}", right, left),
+            span: None,
+            help: None,
+        }
+        .into()),
+    }
+}
+
+fn eval_if(
+    condition: &ast::Expression,
+    consequence: &ast::Statement,
+    alternative: Option<&ast::Statement>,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let condition = eval_expression(condition, env)?;
+    if eval_condition(condition, env.is_strict(), "If condition")? {
+        eval_statement(consequence, env)
+    } else {
+        match alternative {
+            Some(a) => eval_statement(a, env),
+            None => Ok(Object::Null),
+        }
+    }
+}
+
+fn eval_identifier(name: &str, env: &environment::Environment) -> Result<Object, Interrupt> {
+    if let Some(o) = env.get(name) {
+        Ok(o)
+    } else {
+        Err(EvalError {
+            msg: format!("Undefined variable {}", name),
+            span: None,
+            help: env
+                .similar_name(name)
+                .map(|s| format!("did you mean `{}`?", s)),
+        }
+        .into())
+    }
+}
+
+fn eval_call(
+    function: &ast::Expression,
+    arguments: &[ast::Expression],
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let args = eval_expressions(arguments, env)?;
+    let function = eval_expression(function, env)?;
+    Ok(apply_function(function, args)?)
+}
+
+fn eval_map_literal(
+    m: &[(Box<ast::Expression>, Box<ast::Expression>)],
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let mut map = IndexMap::new();
+    for (k, v) in m.iter() {
+        let key = eval_expression(k, env)?;
+        let value = eval_expression(v, env)?;
+        // keyにできない値だった場合はmap全体ではなくそのkeyを指す
+        let key = MapKey::try_from(key).map_err(|e| e.with_span(k.span()))?;
+        map.insert(key, Box::new(value));
+    }
+    Ok(Object::Map(map))
+}
+
+fn eval_for(
+    parameter: &str,
+    value: Option<&String>,
+    iterable: &ast::Expression,
+    statement: &ast::Statement,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let iterable = eval_expression(iterable, env)?;
+    let is_map = matches!(iterable, Object::Map(_));
+    let mut result = Object::Null;
+    for (key, item) in eval_iterable(iterable)? {
+        // ループの変数は毎回新しいscopeに置くので、外側の同名の変数は上書きされない
+        let mut scope = environment::Environment::new_enclosed(env);
+        match value {
+            Some(value) => {
+                scope.set(parameter.to_string(), key);
+                scope.set(value.clone(), item);
             }
-            Ok(result)
-        } //_ => Err(EvalError { msg: "not implemented yet".to_string(), }),
+            None if is_map => scope.set(parameter.to_string(), key),
+            None => scope.set(parameter.to_string(), item),
+        }
+        let evaluated = eval_statement(statement, &mut scope);
+        scope.release();
+        match evaluated {
+            // returnはループを抜けてさらに外へ伝える
+            Ok(o @ Object::Return(_)) => return Ok(o),
+            Ok(o) => result = o,
+            Err(Interrupt::Break(_)) => break,
+            Err(Interrupt::Continue(_)) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(result)
+}
+
+fn eval_while(
+    condition: &ast::Expression,
+    statement: &ast::Statement,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let mut result = Object::Null;
+    loop {
+        let c = eval_expression(condition, env)?;
+        if !eval_condition(c, env.is_strict(), "While condition")? {
+            break;
+        }
+        // forと同じく1回ごとに新しいscopeで実行するので、中のletはループの外に残らない
+        let mut scope = environment::Environment::new_enclosed(env);
+        let evaluated = eval_statement(statement, &mut scope);
+        scope.release();
+        match evaluated {
+            Ok(o @ Object::Return(_)) => return Ok(o),
+            Ok(o) => result = o,
+            Err(Interrupt::Break(_)) => break,
+            Err(Interrupt::Continue(_)) => continue,
+            Err(e) => return Err(e),
+        }
     }
+    Ok(result)
 }
 
 fn eval_block_statements(
-    statements: Vec<ast::Statement>,
+    statements: &[ast::Statement],
     env: &mut environment::Environment,
-) -> Result<Object, Error> {
+) -> Result<Object, Interrupt> {
     let mut result = Object::Null;
     for stmt in statements {
         result = eval_statement(stmt, env)?;
@@ -299,28 +1008,38 @@ fn eval_block_statements(
 }
 
 fn eval_statement(
-    statement: ast::Statement,
+    statement: &ast::Statement,
     env: &mut environment::Environment,
-) -> Result<Object, Error> {
+) -> Result<Object, Interrupt> {
     match statement {
         ast::Statement::Expression(e) => eval_expression(e, env),
         ast::Statement::Block(statements) => eval_block_statements(statements, env),
-        ast::Statement::Return(e) => Ok(Object::Return(Box::new(eval_expression(e, env)?))),
-        ast::Statement::Let { name, value } => {
-            let val = eval_expression(value, env)?;
-            env.set(name, val.clone());
-            Ok(val)
+        ast::Statement::Return { value, .. } => {
+            eval_expression(value, env).map(|o| Object::Return(Box::new(o)))
         }
+        ast::Statement::Let { name, value, .. } => eval_let(name, value, env),
+        ast::Statement::Break(span) => Err(Interrupt::Break(*span)),
+        ast::Statement::Continue(span) => Err(Interrupt::Continue(*span)),
     }
 }
 
+fn eval_let(
+    name: &str,
+    value: &ast::Expression,
+    env: &mut environment::Environment,
+) -> Result<Object, Interrupt> {
+    let val = eval_expression(value, env)?;
+    env.set(name.to_string(), val.clone());
+    Ok(val)
+}
+
 fn eval_statements(
     statements: Vec<ast::Statement>,
     env: &mut environment::Environment,
 ) -> Result<Object, Error> {
     let mut result = Object::Null;
-    for stmt in statements {
-        result = eval_statement(stmt, env)?;
+    for stmt in &statements {
+        result = eval_statement(stmt, env).map_err(Interrupt::into_error)?;
         if let Object::Return(o) = result {
             return Ok(o.as_ref().clone());
         }
@@ -348,10 +1067,33 @@ mod test {
             ("2 * 2* 2*2*2", 32),
             ("-50 + 100 + -50", 0),
             ("5*2 + 10", 20),
+            ("2147483647 + 1", 2147483648),
+            ("3037000499 * 3037000499", 9223372030926249001),
             ("5+2 * 10", 25),
             ("50 / 2 * 2 + 10", 60),
             ("2 * (5+10)", 30),
             ("3 + (5+10) * 2", 33),
+            ("7 % 3", 1),
+            ("-7 % 3", -1),
+            ("1 + 10 % 4 * 2", 5),
+            ("2 ** 10", 1024),
+            ("2 ** 3 ** 2", 512),
+            ("-2 ** 2", -4),
+            ("(-2) ** 3", -8),
+            ("3 * 2 ** 2", 12),
+            // 指数が大きくても結果が収まれば計算できる
+            ("1 ** 5000000000", 1),
+            ("(-1) ** 4294967297", -1),
+            ("(-1) ** 4294967296", 1),
+            ("0 ** 9223372036854775807", 0),
+            ("2 ** 62", 4611686018427387904),
+            ("(-2) ** 63", -9223372036854775807 - 1),
+            ("let a = 1; a += 2; a", 3),
+            ("let a = 10; a -= 4; a", 6),
+            ("let a = 3; a *= 3; a", 9),
+            ("let a = 9; a /= 2; a", 4),
+            ("let a = 1; a += 2 * 3", 7),
+            ("let sum = 0; for x in [1, 2, 3] { sum += x }; sum", 6),
         ];
 
         for (input, expect) in tests {
@@ -369,6 +1111,45 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_eval_float() {
+        let tests = vec![
+            ("5.5", "5.5"),
+            ("-2.5", "-2.5"),
+            ("1.5 + 1.5", "3.0"),
+            ("1 + 0.5", "1.5"),
+            ("0.5 * 4", "2.0"),
+            ("7 / 2.0", "3.5"),
+            ("10.0 - 2 * 3", "4.0"),
+            ("1.5 < 2", "true"),
+            ("2 > 2.5", "false"),
+            ("1 == 1.0", "true"),
+            ("0.1 != 0.1", "false"),
+            ("int(3.9)", "3"),
+            ("int(-3.9)", "-3"),
+            (r#"int("42")"#, "42"),
+            ("float(3)", "3.0"),
+            (r#"float("0.25")"#, "0.25"),
+            ("float(1) / 4", "0.25"),
+            ("7.5 % 2", "1.5"),
+            ("2 ** 0.5 > 1.41", "true"),
+            ("2 ** -1", "0.5"),
+            ("let a = 1; a += 0.5; a", "1.5"),
+            ("1.0 >= 1", "true"),
+            ("0.5 <= 0.25", "false"),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => assert_eq!(format!("{}", o), expect, "input: {}", input),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
     #[test]
     fn test_eval_boolean() {
         let tests = vec![("true", true), ("false", false)];
@@ -411,6 +1192,12 @@ mod test {
             ("(1<2) == false", false),
             ("(1>2) == false", true),
             ("(1>2) == true", false),
+            ("1 <= 2", true),
+            ("2 <= 2", true),
+            ("3 <= 2", false),
+            ("1 >= 2", false),
+            ("2 >= 2", true),
+            ("!(1 >= 2)", true),
         ];
         for (input, expect) in tests {
             let mut l = Lexer::new(input);
@@ -428,12 +1215,27 @@ mod test {
     }
 
     #[test]
-    fn test_eval_ifelse_expressions() {
+    fn test_eval_logical_operator() {
         let tests = vec![
-            ("if(true){10}", 10),
-            ("if(false){10} else {20}", 20),
-            ("if(false){10}", -1),
-            ("if(1 > 0){10}", 10),
+            ("true && true", true),
+            ("true && false", false),
+            ("false || true", true),
+            ("false || false", false),
+            ("1 < 2 && 2 < 3", true),
+            ("1 > 2 || 2 > 3", false),
+            ("true || false && false", true),
+            ("(true || false) && false", false),
+            // 右辺は評価されないので未定義の変数でもエラーにならない
+            ("false && undefined", false),
+            ("true || undefined", true),
+            (
+                "let called = false; let f = fn() { called = true; true }; false && f(); called",
+                false,
+            ),
+            (
+                "let called = false; let f = fn() { called = true; true }; false || f(); called",
+                true,
+            ),
         ];
         for (input, expect) in tests {
             let mut l = Lexer::new(input);
@@ -442,11 +1244,183 @@ mod test {
             let mut env = environment::Environment::new();
             match eval(program, &mut env) {
                 Ok(o) => match o {
-                    Object::Integer(i) => assert_eq!(i, expect),
-                    // Nullが返る分岐は-1をexpectとして入れておいて判定
-                    // 数字は何でも良いんだけど、とりあえずこれで
-                    Object::Null => assert_eq!(-1, expect),
-                    _ => panic!("Error expect {} but got {:?}", expect, o),
+                    Object::Boolean(b) => assert_eq!(b, expect, "{}", input),
+                    o => panic!("Error expect {} but got {:?}", expect, o),
+                },
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_structural_equality() {
+        let tests = vec![
+            ("[1, 2] == [1, 2]", "true"),
+            ("[1, 2] == [2, 1]", "false"),
+            ("[1, [2, 3]] == [1, [2, 3]]", "true"),
+            ("[1] == [1, 2]", "false"),
+            ("[1, 2] != [1, 2]", "false"),
+            ("[1] == [1.0]", "true"),
+            (r#"{"a": 1, "b": [2]} == {"b": [2], "a": 1}"#, "true"),
+            (r#"{"a": 1} == {"a": 2}"#, "false"),
+            (r#"{"a": 1} == {"b": 1}"#, "false"),
+            ("{} == {}", "true"),
+            ("null == null", "true"),
+            ("first([]) == null", "true"),
+            ("let x = 1; x == null", "false"),
+            ("let x = 1; x != null", "true"),
+            ("[null] == [null]", "true"),
+            (r#"{"a": 1}["b"] == null"#, "true"),
+            (r#"1 == "1""#, "false"),
+            ("true != 1", "true"),
+            ("[1] == {}", "false"),
+            ("(0..3) == (0..3)", "true"),
+            ("(0..3) == [0, 1, 2]", "false"),
+            ("let f = fn() { 1 }; f == f", "true"),
+            ("len == len", "true"),
+            ("len == first", "false"),
+            (r#""abc" < "abd""#, "true"),
+            (r#""b" > "abc""#, "true"),
+            (r#""ab" <= "ab""#, "true"),
+            (r#""" < "a""#, "true"),
+            ("[1, 2] < [1, 3]", "true"),
+            ("[1, 2] < [1, 2, 0]", "true"),
+            ("[2] > [1, 9]", "true"),
+            ("[1, 2] >= [1, 2]", "true"),
+            ("[[1], 2] < [[1, 0], 0]", "true"),
+            ("[1] < [1.5]", "true"),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => assert_eq!(format!("{}", o), expect, "input: {}", input),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_infix_operator() {
+        // 代入や論理演算は別の経路で評価されるが、渡されてもpanicしない
+        let e = eval_float_infix_expression(ast::InfixOprator::And, 1.0, 2.0).unwrap_err();
+        assert_eq!(
+            format!("{}", e),
+            "EvalError: unsupported operator `&&` for float"
+        );
+        let e = eval_infix_expression(
+            ast::InfixOprator::Assign,
+            Object::Integer(1),
+            Object::Integer(2),
+        )
+        .unwrap_err();
+        assert_eq!(format!("{}", e), "EvalError: unsupported operator `=`");
+    }
+
+    #[test]
+    fn test_truthiness() {
+        let tests = vec![
+            ("if (0) { 1 } else { 2 }", "2"),
+            ("if (0.0) { 1 } else { 2 }", "2"),
+            (r#"if ("") { 1 } else { 2 }"#, "2"),
+            ("if ([]) { 1 } else { 2 }", "2"),
+            ("if ({}) { 1 } else { 2 }", "2"),
+            ("if (0..0) { 1 } else { 2 }", "2"),
+            ("if (first([])) { 1 } else { 2 }", "2"),
+            (r#"if (len("a")) { 1 } else { 2 }"#, "1"),
+            ("if ([0]) { 1 } else { 2 }", "1"),
+            ("if (fn() {}) { 1 } else { 2 }", "1"),
+            ("!0", "true"),
+            (r#"!"""#, "true"),
+            ("![1]", "false"),
+            ("!first([])", "true"),
+            (r#"0 || "x""#, "true"),
+            ("1 && []", "false"),
+            ("let n = 3; let c = 0; while (n) { n -= 1; c += 1 }; c", "3"),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => assert_eq!(format!("{}", o), expect, "input: {}", input),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_strict_condition() {
+        let tests = vec![
+            (
+                "if (1) { 1 }",
+                "EvalError: If condition must be boolean, but got integer",
+            ),
+            (
+                "while (1) { 1 }",
+                "EvalError: While condition must be boolean, but got integer",
+            ),
+            (
+                "!first([])",
+                "EvalError: operand of `!` must be boolean, but got null",
+            ),
+            (
+                "1 && true",
+                "EvalError: operand of `&&` must be boolean, but got integer",
+            ),
+            (
+                "false || \"a\"",
+                "EvalError: operand of `||` must be boolean, but got string",
+            ),
+            // 関数の中でも同じ
+            (
+                "let f = fn(x) { if (x) { 1 } }; f([])",
+                "EvalError: If condition must be boolean, but got array",
+            ),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            env.set_strict(true);
+            match eval(program, &mut env) {
+                Ok(o) => panic!("expect error but got {:?}", o),
+                Err(e) => assert_eq!(format!("{}", e), expect),
+            }
+        }
+        // boolなら通常と変わらない
+        let mut l = Lexer::new("if (1 < 2 && !false) { 10 }");
+        let mut p = Parser::new(&mut l);
+        let program = p.parse_program().unwrap();
+        let mut env = environment::Environment::new();
+        env.set_strict(true);
+        assert_eq!(eval(program, &mut env).unwrap(), Object::Integer(10));
+    }
+
+    #[test]
+    fn test_eval_ifelse_expressions() {
+        let tests = vec![
+            ("if(true){10}", 10),
+            ("if(false){10} else {20}", 20),
+            ("if(false){10}", -1),
+            ("if(1 > 0){10}", 10),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => match o {
+                    Object::Integer(i) => assert_eq!(i, expect),
+                    // Nullが返る分岐は-1をexpectとして入れておいて判定
+                    // 数字は何でも良いんだけど、とりあえずこれで
+                    Object::Null => assert_eq!(-1, expect),
+                    _ => panic!("Error expect {} but got {:?}", expect, o),
                 },
                 Err(e) => panic!("{:?}", e),
             }
@@ -482,6 +1456,138 @@ mod test {
             ("5 + true", "EvalError: Invalid infix expression"),
             ("5 + true; 5", "EvalError: Invalid infix expression"),
             ("-true", "EvalError: Invalid prefix expression"),
+            ("1.5 + true", "EvalError: Invalid infix expression"),
+            ("9223372036854775807 + 1", "EvalError: integer overflow"),
+            ("-9223372036854775807 - 2", "EvalError: integer overflow"),
+            ("4294967296 * 4294967296", "EvalError: integer overflow"),
+            ("1 / 0", "EvalError: division by zero"),
+            ("1 % 0", "EvalError: modulo by zero"),
+            ("let a = 1; a /= 0", "EvalError: division by zero"),
+            ("2 ** 63", "EvalError: integer overflow"),
+            ("2 ** 4294967296", "EvalError: integer overflow"),
+            ("(-2) ** 64", "EvalError: integer overflow"),
+            ("true >= 1", "EvalError: Invalid infix expression"),
+            (r#"1 < "a""#, "EvalError: Invalid infix expression"),
+            (
+                r#"{[1]: "a", {}: "b"}"#,
+                "EvalError: map can not be used as a map key",
+            ),
+            ("{1.5: 1}", "EvalError: float can not be used as a map key"),
+            (
+                "{[1, fn() {}]: 1}",
+                "EvalError: function can not be used as a map key",
+            ),
+            (
+                "let m = {1: 2}; m[fn() {}]",
+                "EvalError: function can not be used as a map key",
+            ),
+            (
+                "let m = {1: 2}; m[len]",
+                "EvalError: builtin function can not be used as a map key",
+            ),
+            (
+                "let a = [1, 2, 3]; a[3] = 0",
+                "EvalError: index 3 is out of range for length 3",
+            ),
+            (
+                "let a = [1, 2, 3]; a[-1] = 0",
+                "EvalError: index -1 is out of range for length 3",
+            ),
+            (
+                "let a = [[1], [2]]; a[2][0] = 0",
+                "EvalError: index 2 is out of range for length 2",
+            ),
+            (
+                r#"let a = [1]; a["x"] = 0"#,
+                "EvalError: Can not index array with string",
+            ),
+            (
+                r#"let m = {}; m["x"][0] = 1"#,
+                "EvalError: key x is not found in map",
+            ),
+            (
+                r#"let m = {}; m["x"] += 1"#,
+                "EvalError: key x is not found in map",
+            ),
+            (
+                r#"let s = "abc"; s[0] = "x""#,
+                "EvalError: string does not support index assignment",
+            ),
+            (
+                "len[0] = 1",
+                "EvalError: builtin function does not support index assignment",
+            ),
+            ("xs[0] = 1", "EvalError: Undefined variable xs"),
+            ("[1, 2][0] = 1", "EvalError: can not assign 1 to [1, 2][0]"),
+            (
+                "let a = [1]; delete a[1]",
+                "EvalError: index 1 is out of range for length 1",
+            ),
+            (
+                "let a = [[1]]; delete a[1][0]",
+                "EvalError: index 1 is out of range for length 1",
+            ),
+            (
+                r#"let s = "abc"; delete s[0]"#,
+                "EvalError: string does not support delete",
+            ),
+            (
+                r#"let m = {"a": "b"}; delete m["a"][0]"#,
+                "EvalError: string does not support delete",
+            ),
+            (
+                "delete len[0]",
+                "EvalError: builtin function does not support delete",
+            ),
+            ("delete xs[0]", "EvalError: Undefined variable xs"),
+            ("delete [1][0]", "EvalError: can not delete [1][0]"),
+            (r#"[1] < ["a"]"#, "EvalError: Invalid infix expression"),
+            ("{} < {}", "EvalError: Invalid infix expression"),
+            ("b += 1", "EvalError: Undefined variable b"),
+            ("for x in 10 { x }", "EvalError: integer is not iterable"),
+            ("0..1.5", "EvalError: range bounds must be integer"),
+            ("true..2", "EvalError: range bounds must be integer"),
+            ("0..=9223372036854775807", "EvalError: integer overflow"),
+            (
+                "[1, 2, 3][1..4]",
+                "EvalError: slice 1..4 is out of range for length 3",
+            ),
+            (
+                "(0..5)[3..2]",
+                "EvalError: slice 3..2 is out of range for length 5",
+            ),
+            ("for x in [1] { x }; x", "EvalError: Undefined variable x"),
+            (
+                "let i = 0; while (i < 1) { let y = i; i += 1 }; y",
+                "EvalError: Undefined variable y",
+            ),
+            ("break", "EvalError: `break` outside of a loop"),
+            (
+                "let f = fn() { continue }; while (true) { f() }",
+                "EvalError: `continue` outside of a loop",
+            ),
+            (
+                "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(100000)",
+                "EvalError: maximum recursion depth exceeded",
+            ),
+            ("let a = 0; 10 / (a * 2)", "EvalError: division by zero"),
+            (
+                "(-9223372036854775807 - 1) / -1",
+                "EvalError: integer overflow",
+            ),
+            ("-(-9223372036854775807 - 1)", "EvalError: integer overflow"),
+            (
+                r#"int("abc")"#,
+                r#"BuiltinError: can not convert "abc" to integer"#,
+            ),
+            (
+                "int(1.0 / 0)",
+                "BuiltinError: can not convert inf to integer",
+            ),
+            (
+                "len({})",
+                "BuiltinError: argument to `len` must be String, Array or Range, got map",
+            ),
             ("true+false", "EvalError: Invalid infix expression"),
             (
                 "if(10>1){true + false;}",
@@ -504,6 +1610,44 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_error_span() {
+        let tests = vec![
+            ("let a = 1;\n a + b", (2, 6)),
+            ("1 +\n true", (1, 1)),
+            ("let f = fn(x) {\n  -x\n};\nf(true)", (2, 3)),
+            (r#"len(1, 2)"#, (1, 1)),
+            // `${}`の中のエラーもソース上の位置を指す
+            ("let a = 1;\n\"x = ${a + b}\"", (2, 12)),
+            ("let a = 1;\nif (a > 0) {\n  break }", (3, 3)),
+            ("let x = {1.5: 1};", (1, 10)),
+            ("puts({1: 2, [fn() {}]: 1})", (1, 13)),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => panic!("expect error but got {:?}", o),
+                Err(e) => {
+                    let span = e.span().unwrap();
+                    assert_eq!((span.start.line, span.start.column), expect);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unicode_identifier() {
+        let input = "let 名前 = 2; let user2_id = 3; 名前 * user2_id";
+        let mut l = Lexer::new(input);
+        let mut p = Parser::new(&mut l);
+        let program = p.parse_program().unwrap();
+        let mut env = environment::Environment::new();
+        assert_eq!(eval(program, &mut env).unwrap(), Object::Integer(6));
+    }
+
     #[test]
     fn test_call() {
         let tests = vec![
@@ -512,6 +1656,37 @@ mod test {
                 "let f = fn(a){ fn(b){a+b};}; let c = f(10); let d = c(20);",
                 30,
             ),
+            // 再帰
+            (
+                "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(5)",
+                120,
+            ),
+            // 相互再帰
+            (
+                r#"
+                let is_even = fn(n) { if (n == 0) { true } else { is_odd(n - 1) } };
+                let is_odd = fn(n) { if (n == 0) { false } else { is_even(n - 1) } };
+                if (is_even(10)) { 1 } else { 0 }
+                "#,
+                1,
+            ),
+            // closureで捕まえた変数を書き換える
+            (
+                r#"
+                let counter = fn() { let c = 0; fn() { c = c + 1; c } };
+                let next = counter();
+                next();
+                next();
+                next()
+                "#,
+                3,
+            ),
+            (
+                "let total = 0; let add = fn(x) { total = total + x }; add(2); add(3); total",
+                5,
+            ),
+            // letは関数の中で新しい変数を作るので外側は変わらない
+            ("let a = 1; let f = fn() { let a = 2; a }; f(); a", 1),
         ];
         for (input, expect) in tests {
             let mut l = Lexer::new(input);
@@ -592,6 +1767,11 @@ mod test {
         let tests = vec![
             ("let a = {1: 222}; a[1]", 222),
             (r#"let b = {"aa": 345}; b["aa"];"#, 345),
+            // 配列は組のkeyとして使える
+            ("let c = {[1, 2]: 10, [2, 1]: 20}; c[[2, 1]]", 20),
+            (r#"let d = {[1, ["a", true]]: 30}; d[[1, ["a", true]]]"#, 30),
+            ("let e = {[]: 40}; e[[]]", 40),
+            ("let n = 0; for k in {[1]: 1, [2]: 2} { n += 1 }; n", 2),
         ];
 
         for (input, expect) in tests {
@@ -609,11 +1789,137 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_map_order() {
+        let tests = vec![
+            (r#"{"b": 1, "a": 2, "c": 3}"#, "{b: 1,a: 2,c: 3}"),
+            ("{3: 1, 1: 2, 2: 3}", "{3: 1,1: 2,2: 3}"),
+            (r#"{[1, "a"]: 1}"#, "{[1,a]: 1}"),
+            ("for k in {[1, 2]: 0} { k }", "[1,2]"),
+            // 同じkeyは最初の位置のまま値だけ後のものになる
+            (r#"{"b": 1, "a": 2, "b": 3}"#, "{b: 3,a: 2}"),
+            (
+                r#"let m = {"z": 1, "y": 2, "x": 3}; let ks = []; for k in m { ks = push(ks, k) }; ks"#,
+                "[z,y,x]",
+            ),
+            (
+                r#"let m = {"z": 1, "y": 2}; let s = ""; for k, v in m { s += k + "${v}" }; s"#,
+                "z1y2",
+            ),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => assert_eq!(format!("{}", o), expect, "input: {}", input),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_assign() {
+        let tests = vec![
+            ("let a = [1, 2, 3]; a[1] = 20; a", "[1,20,3]"),
+            ("let a = [1, 2, 3]; a[0] = 10", "10"),
+            (r#"let m = {"a": 1}; m["a"] = 2; m"#, "{a: 2}"),
+            (r#"let m = {"a": 1}; m["b"] = 2; m"#, "{a: 1,b: 2}"),
+            (
+                r#"let m = {"x": [1, 2], "y": 0}; m["x"][0] = 10; m"#,
+                "{x: [10,2],y: 0}",
+            ),
+            (
+                "let a = [[1, 2], [3, 4]]; a[1][0] += 30; a",
+                "[[1,2],[33,4]]",
+            ),
+            (r#"let m = {"n": 1}; m["n"] *= 5; m["n"]"#, "5"),
+            ("let a = [0, 0]; let i = 0; a[i + 1] = 9; a", "[0,9]"),
+            ("let a = [1]; let b = a; a[0] = 2; b", "[1]"),
+            // 関数の中から外側の変数の要素を書き換える
+            ("let a = [1, 2]; let f = fn() { a[0] = 5 }; f(); a", "[5,2]"),
+            (
+                "let a = [1]; for i in 0..3 { a = push(a, 0); a[i + 1] = i }; a",
+                "[1,0,1,2]",
+            ),
+            (r#"let m = {[1, 2]: 0}; m[[1, 2]] = "t"; m[[1, 2]]"#, "t"),
+            // deleteもその場で取り除き、取り除いた値を返す
+            (
+                r#"let m = {"a": 1, "b": 2, "c": 3}; delete m["b"]; m"#,
+                "{a: 1,c: 3}",
+            ),
+            (r#"let m = {"a": 1, "b": 2}; delete m["b"]"#, "2"),
+            (r#"let m = {"a": 1}; delete m["z"]"#, "null"),
+            ("let a = [1, 2, 3]; delete a[0]; a", "[2,3]"),
+            (
+                r#"let m = {"x": [1, 2, 3]}; delete m["x"][1]; m"#,
+                "{x: [1,3]}",
+            ),
+            (
+                r#"let m = {"a": 1, "b": 2}; delete m["a"]; m["a"] = 3; m"#,
+                "{b: 2,a: 3}",
+            ),
+            (
+                "let a = [1, 2]; let f = fn() { delete a[0] }; f(); a",
+                "[2]",
+            ),
+            // 関数呼び出しの形でも書ける
+            (r#"let m = {"a": 1, "b": 2}; delete(m, "a"); m"#, "{b: 2}"),
+            (r#"let m = {"x": [1, 2]}; delete(m["x"], 0)"#, "1"),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => assert_eq!(format!("{}", o), expect, "input: {}", input),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
     #[test]
     fn test_for() {
         let tests = vec![
             ("let a = 0; for b in [1,2,3] { b }", 3),
             ("let a = 0; for b in [1,2,644] { b }", 644),
+            (
+                "let xs = [1, 2, 3]; let sum = 0; for x in xs { sum += x }; sum",
+                6,
+            ),
+            (
+                "let f = fn() { [4, 5] }; let sum = 0; for x in f() { sum += x }; sum",
+                9,
+            ),
+            (
+                "let sum = 0; for i, x in [10, 20] { sum += i * x }; sum",
+                20,
+            ),
+            (
+                r#"let s = ""; for c in "abc" { s = c + s }; if (s == "cba") { 1 } else { 0 }"#,
+                1,
+            ),
+            (r#"let n = 0; for c in "あいう" { n += 1 }; n"#, 3),
+            (
+                r#"let m = {"b": 2, "a": 1}; let s = ""; for k in m { s += k }; if (s == "ba") { 1 } else { 0 }"#,
+                1,
+            ),
+            (
+                r#"let m = {"a": 1, "b": 2}; let sum = 0; for k, v in m { sum += v }; sum"#,
+                3,
+            ),
+            ("let sum = 0; for x in [] { sum += 1 }; sum", 0),
+            // ループの変数は外側の同名の変数を上書きしない
+            ("let x = 100; for x in [1, 2, 3] { x }; x", 100),
+            // ループの中のletもループの外には見えない
+            ("let a = 1; for x in [1] { let a = 2 }; a", 1),
+            // 毎回新しいscopeなので、closureはその回の値を捕まえる
+            (
+                "let fs = []; for x in [1, 2, 3] { fs = push(fs, fn() { x }) }; fs[0]() + fs[2]()",
+                4,
+            ),
         ];
 
         for (input, expect) in tests {
@@ -630,4 +1936,156 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_while() {
+        let tests = vec![
+            ("let i = 0; while (i < 5) { i += 1 }; i", 5),
+            ("let i = 10; while (i < 5) { i += 1 }; i", 10),
+            // 中のletは外側の同名の変数を上書きしない
+            ("let x = 1; let i = 0; while (i < 2) { let x = 10; i += 1 }; x", 1),
+            ("let i = 0; while (true) { i += 1; if (i == 3) { break; } }; i", 3),
+            (
+                "let i = 0; let sum = 0; while (i < 10) { i += 1; if (i % 2 == 0) { continue; } sum += i }; sum",
+                25,
+            ),
+            (
+                "let sum = 0; for x in [1, 2, 3, 4] { if (x == 3) { break } sum += x }; sum",
+                3,
+            ),
+            (
+                "let sum = 0; for x in [1, 2, 3, 4] { if (x == 2) { continue } sum += x }; sum",
+                8,
+            ),
+            // breakは一番内側のループだけを抜ける
+            (
+                r#"
+                let count = 0;
+                for x in [1, 2, 3] {
+                    let j = 0;
+                    while (true) { j += 1; if (j > x) { break; } count += 1 }
+                }
+                count
+                "#,
+                6,
+            ),
+            // returnはループの中からでも関数を抜ける
+            (
+                "let f = fn() { let i = 0; while (true) { i += 1; if (i == 4) { return i * 10 } } }; f()",
+                40,
+            ),
+            (
+                "let find = fn() { for x in [1, 5, 3] { if (x > 2) { return x } }; 0 }; find()",
+                5,
+            ),
+        ];
+
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => match o {
+                    Object::Integer(i) => assert_eq!(i, expect, "{}", input),
+                    _ => panic!("Error expect `{}` but got {:?}", expect, o),
+                },
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_string() {
+        let tests = vec![
+            (r#"len("あいう")"#, "3"),
+            (r#"len("😀")"#, "1"),
+            (r#"len("a\nb")"#, "3"),
+            (r#""あいう"[1]"#, "い"),
+            (r#""abc"[3]"#, "null"),
+            (r#""abc"[-1]"#, "null"),
+            (r#""こんにちは"[1..3]"#, "んに"),
+            (r#""say \"hi\"""#, "say \"hi\""),
+            (r#"r"\n""#, "\\n"),
+            ("\"a\nb\" == \"a\\nb\"", "true"),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => assert_eq!(format!("{}", o), expect, "input: {}", input),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolated_string() {
+        let tests = vec![
+            (r#"let name = "Bob"; "Hello, ${name}!""#, "Hello, Bob!"),
+            (
+                r#"let items = [1, 2]; "You have ${len(items)} items""#,
+                "You have 2 items",
+            ),
+            (r#""${1 + 2}${true} ${1.5} ${[1, 2]}""#, "3true 1.5 [1,2]"),
+            (r#"let m = {"}": "brace"}; "${m["}"]}""#, "brace"),
+            (r#"let x = 1; "a${"b${x}c"}d""#, "ab1cd"),
+            (r#""\${x}""#, "${x}"),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => assert_eq!(format!("{}", o), expect, "input: {}", input),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_range() {
+        let tests =
+            vec![
+            ("0..3", "0..3"),
+            ("1..=3", "1..=3"),
+            ("5..=2", "5..=2"),
+            ("(0..=3) == (0..4)", "true"),
+            ("(0..=9)[2..4]", "2..4"),
+            ("let n = 2; 0..n * 2", "0..4"),
+            ("len(0..10)", "10"),
+            ("len(0..=10)", "11"),
+            ("len(5..2)", "0"),
+            ("len(0..9223372036854775807)", "9223372036854775807"),
+            ("(10..20)[3]", "13"),
+            ("(10..20)[10]", "null"),
+            ("(10..20)[-1]", "null"),
+            ("(10..20)[2..5]", "12..15"),
+            ("[1, 2, 3, 4][1..3]", "[2,3]"),
+            ("[1, 2, 3, 4][0..=3]", "[1,2,3,4]"),
+            ("[1, 2, 3][3..3]", "[]"),
+            ("let sum = 0; for i in 0..5 { sum += i }; sum", "10"),
+            ("let sum = 0; for i in 1..=5 { sum += i }; sum", "15"),
+            ("let n = 0; for i in 3..0 { n += 1 }; n", "0"),
+            ("let last = 0; for i, x in 10..13 { last = i * 100 + x }; last", "212"),
+            // 要素を全部作らないので巨大なrangeでもすぐ終わる
+            (
+                "let n = 0; for i in 0..9223372036854775807 { n += 1; if (n == 3) { break } }; n",
+                "3",
+            ),
+        ];
+        for (input, expect) in tests {
+            let mut l = Lexer::new(input);
+            let mut p = Parser::new(&mut l);
+            let program = p.parse_program().unwrap();
+            let mut env = environment::Environment::new();
+            match eval(program, &mut env) {
+                Ok(o) => assert_eq!(format!("{}", o), expect, "input: {}", input),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
 }
@@ -0,0 +1,183 @@
+use crate::environment::Environment;
+use crate::error::Error;
+use crate::evaluator::{apply_function, eval};
+use crate::lexer::Lexer;
+use crate::object::Object;
+use crate::parser::Parser;
+
+// 他のcrateからインタプリタを組み込むための入り口
+// グローバルなEnvironmentを保持するので、eval_strを続けて呼ぶと変数が引き継がれる
+// グローバルに定義した関数はグローバルのScopeと循環参照になるので、dropするときに切る
+// get_globalで取り出した関数などがまだ使われていれば、それが参照するScopeは残る
+#[derive(Debug, Default)]
+pub struct Interpreter {
+    env: Environment,
+}
+
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        std::mem::take(&mut self.env).release();
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self {
+            env: Environment::new(),
+        }
+    }
+
+    // sourceをparseして評価し、最後に評価した値を返す
+    // parseエラーは全てまとめて返すのでVecになっている。評価時のエラーは1つだけ
+    pub fn eval_str(&mut self, source: &str) -> Result<Object, Vec<Error>> {
+        let mut l = Lexer::new(source);
+        let mut parser = Parser::new(&mut l);
+        let program = parser.parse_program()?;
+        eval(program, &mut self.env).map_err(|e| vec![e])
+    }
+
+    // trueにするとif, while, !, &&, ||の条件にbool以外を使ったときエラーにする
+    pub fn set_strict(&mut self, strict: bool) {
+        self.env.set_strict(strict);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.env.get(name)
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Object) {
+        self.env.set(name.to_string(), value);
+    }
+
+    // nameで定義された関数(builtin含む)をargsで呼び出す
+    pub fn call_function(&mut self, name: &str, args: Vec<Object>) -> Result<Object, Error> {
+        match self.get_global(name) {
+            Some(function) => apply_function(function, args),
+            None => Err(Error::EvalError {
+                msg: format!("Undefined function {}", name),
+                span: None,
+                help: None,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_eval_str_keeps_globals() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("let a = 10;").unwrap();
+        match interpreter.eval_str("a * 2") {
+            Ok(Object::Integer(i)) => assert_eq!(i, 20),
+            o => panic!("Error expect 20 but got {:?}", o),
+        }
+    }
+
+    #[test]
+    fn test_get_and_set_global() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("name", Object::String("rust".to_string()));
+        interpreter
+            .eval_str(r#"let greet = "hello " + name"#)
+            .unwrap();
+        assert_eq!(
+            interpreter.get_global("greet"),
+            Some(Object::String("hello rust".to_string()))
+        );
+        assert_eq!(interpreter.get_global("nothing"), None);
+    }
+
+    #[test]
+    fn test_set_strict() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(
+            interpreter.eval_str("if (0) { 1 } else { 2 }").unwrap(),
+            Object::Integer(2)
+        );
+        interpreter.set_strict(true);
+        assert!(interpreter.eval_str("if (0) { 1 } else { 2 }").is_err());
+        interpreter.set_strict(false);
+        assert_eq!(interpreter.eval_str("!0").unwrap(), Object::Boolean(true));
+    }
+
+    #[test]
+    fn test_call_function() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let add = fn(a, b) { a + b };")
+            .unwrap();
+        let tests = vec![
+            ("add", vec![Object::Integer(1), Object::Integer(2)], 3),
+            ("len", vec![Object::String("four".to_string())], 4),
+        ];
+        for (name, args, expect) in tests {
+            match interpreter.call_function(name, args) {
+                Ok(Object::Integer(i)) => assert_eq!(i, expect),
+                o => panic!("Error expect {} but got {:?}", expect, o),
+            }
+        }
+        assert!(interpreter.call_function("nothing", vec![]).is_err());
+    }
+
+    #[test]
+    fn test_deep_recursion_is_an_error() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } };")
+            .unwrap();
+        // スタックを使い切ってプロセスごと落ちる前にエラーになる
+        match interpreter.eval_str("f(100000)") {
+            Err(errors) => assert_eq!(
+                errors[0].to_string(),
+                "EvalError: maximum recursion depth exceeded"
+            ),
+            o => panic!("Error expect recursion error but got {:?}", o),
+        }
+        // エラーの後も続けて呼び出せる
+        assert_eq!(interpreter.eval_str("f(50)").unwrap(), Object::Integer(0));
+    }
+
+    #[test]
+    fn test_drop_releases_globals() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(3)")
+            .unwrap();
+        let global = interpreter.env.downgrade();
+        drop(interpreter);
+        assert!(!global.is_alive());
+    }
+
+    #[test]
+    fn test_calls_do_not_leak_scopes() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let f = fn(n) { let g = fn() { n }; g() }; f(1)")
+            .unwrap();
+        // 呼び出しやループの1回ごとのScopeが残るとグローバルのScopeへの参照が増えていく
+        let count = interpreter.env.strong_count();
+        interpreter
+            .eval_str("for i in 0..100 { let h = fn() { i }; f(i) }")
+            .unwrap();
+        interpreter
+            .eval_str("let j = 0; while (j < 100) { let h = fn() { j }; j += 1 }")
+            .unwrap();
+        assert_eq!(interpreter.env.strong_count(), count);
+    }
+
+    #[test]
+    fn test_returned_closure_outlives_call() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let mk = fn(x) { let g = fn() { x }; g }; let h = mk(5);")
+            .unwrap();
+        assert_eq!(interpreter.eval_str("h()").unwrap(), Object::Integer(5));
+        // Interpreterをdropしても、取り出した関数が使うScopeは残る
+        let h = interpreter.get_global("h").unwrap();
+        drop(interpreter);
+        assert_eq!(apply_function(h, vec![]).unwrap(), Object::Integer(5));
+    }
+}
@@ -1,11 +1,21 @@
+use crate::span::{Position, Span};
 use crate::token::*;
 use std::str::Chars;
+use unicode_xid::UnicodeXID;
 
 #[derive(Clone, Debug)]
 pub struct Lexer<'a> {
     input: Chars<'a>,
     current: char,
     next: char,
+    // currentの位置
+    position: Position,
+    // 読み飛ばし中に見つけた`///`コメントの行
+    docs: Vec<String>,
+    // 最後に返したtokenの直前にあった`///`コメント
+    doc: Option<String>,
+    // 文字列やコメントが閉じられないまま入力が終わったか
+    unterminated: bool,
 }
 
 impl<'a> Lexer<'a> {
@@ -14,35 +24,128 @@ impl<'a> Lexer<'a> {
             input: input.chars(),
             current: '\u{0000}',
             next: '\u{0000}',
+            position: Position::new(),
+            docs: vec![],
+            doc: None,
+            unterminated: false,
         };
         // currentが1文字目になるよう最初に2回読み込む
         lexer.read_char();
         lexer.read_char();
+        lexer.position = Position::new();
         lexer
     }
 
-    fn skip_whitespace(&mut self) {
-        while self.current == ' '
-            || self.current == '\t'
-            || self.current == '\n'
-            || self.current == '\r'
-        {
+    // 文字列の`${}`の中身のように、ソースの途中から始まる入力を読む
+    // spanがソース全体での位置になるよう、positionから数え始める
+    pub fn new_at(input: &'a str, position: Position) -> Self {
+        let mut lexer = Self::new(input);
+        lexer.position = position;
+        lexer
+    }
+
+    // 空白とコメントを読み飛ばす
+    // 閉じられていないブロックコメントがあった場合は不正なtokenを返す
+    fn skip_whitespace(&mut self) -> Option<Token> {
+        loop {
+            match (self.current, self.next) {
+                (' ', _) | ('\t', _) | ('\n', _) | ('\r', _) => self.read_char(),
+                ('/', '/') => self.skip_line_comment(),
+                ('/', '*') => {
+                    if !self.skip_block_comment() {
+                        return Some(self.unterminated("block comment"));
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    // `//`から行末まで。`///`で始まる行はdocとして中身を残す
+    fn skip_line_comment(&mut self) {
+        self.read_char();
+        self.read_char();
+        // `////`のように/が続くものはただのコメント
+        let is_doc = self.current == '/' && self.next != '/';
+        if is_doc {
+            self.read_char();
+        }
+        let mut text = String::new();
+        while self.current != '\n' && self.current != '\u{0000}' {
+            text.push(self.current);
+            self.read_char();
+        }
+        if is_doc {
+            let text = text.strip_prefix(' ').unwrap_or(&text);
+            self.docs.push(text.trim_end().to_string());
+        }
+    }
+
+    // `/* */`は入れ子にできる。閉じられないままEOFに達したらfalse
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+        loop {
+            match (self.current, self.next) {
+                ('/', '*') => {
+                    depth += 1;
+                    self.read_char();
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+                    if depth == 0 {
+                        self.read_char();
+                        return true;
+                    }
+                }
+                ('\u{0000}', _) => return false,
+                _ => {}
+            }
             self.read_char();
         }
     }
 
+    // 最後に返したtokenの直前にあった`///`コメント。複数行なら改行でつなぐ
+    pub fn doc(&self) -> Option<&String> {
+        self.doc.as_ref()
+    }
+
+    // 文字列やコメントが閉じられないまま入力が終わったらtrue。REPLで次の行を待つのに使う
+    pub fn is_unterminated(&self) -> bool {
+        self.unterminated
+    }
+
+    fn unterminated(&mut self, what: &str) -> Token {
+        self.unterminated = true;
+        Token::ILLEGAL(format!("unterminated {}", what))
+    }
+
     fn read_char(&mut self) {
+        // EOFより先には進まない
+        if self.current != '\u{0000}' {
+            self.position.advance(self.current);
+        }
         self.current = self.next;
         // EOFになるとorに進むのでNULLのunicodeを返す
         self.next = self.input.next().unwrap_or('\u{0000}');
     }
 
+    // 識別子はUnicodeのXIDに従う。先頭は文字か`_`、2文字目以降は数字も使える
     fn is_letter(c: char) -> bool {
-        'a' <= c && c <= 'z' || 'A' <= c && c <= 'Z' || c == '_'
+        c == '_' || UnicodeXID::is_xid_start(c)
+    }
+
+    fn is_identifier_continue(c: char) -> bool {
+        UnicodeXID::is_xid_continue(c)
     }
 
     fn is_digit(c: char) -> bool {
-        '0' <= c && c <= '9'
+        c.is_ascii_digit()
+    }
+
+    // nextのさらに次の文字
+    fn peek_second(&self) -> char {
+        self.input.clone().next().unwrap_or('\u{0000}')
     }
 
     fn read_number(&mut self) -> Token {
@@ -51,7 +154,8 @@ impl<'a> Lexer<'a> {
         while Self::is_digit(self.current) {
             ident.push(self.current);
             // 読み込みは一回だけ。それ以降は通さない
-            if self.next == '.' {
+            // `.`の後に数字が無い場合(0..10など)は小数点ではない
+            if !is_float && self.next == '.' && Self::is_digit(self.peek_second()) {
                 self.read_char();
                 is_float = true;
                 ident.push(self.current);
@@ -60,21 +164,153 @@ impl<'a> Lexer<'a> {
         }
 
         if is_float {
-            Token::FLOAT(ident.parse::<f32>().unwrap())
+            Token::FLOAT(ident.parse::<f64>().unwrap())
         } else {
-            Token::INT(ident.parse::<i32>().unwrap())
+            match ident.parse::<i64>() {
+                Ok(i) => Token::INT(i),
+                Err(_) => Token::ILLEGAL(format!("integer literal `{}` is too large", ident)),
+            }
         }
     }
 
     fn read_identifier(&mut self) -> String {
         let mut ident = String::new();
-        while Self::is_letter(self.current) {
+        while Self::is_identifier_continue(self.current) {
             ident.push(self.current);
             self.read_char();
         }
         ident
     }
 
+    // currentが開きの`"`の状態で呼ぶ。閉じの`"`まで読み進める
+    // 改行もそのまま文字列に含める
+    // `${}`を含む場合は断片に分けてINTERPOLATEDにする
+    fn read_string(&mut self) -> Token {
+        self.read_char();
+        let mut s = String::new();
+        let mut parts = vec![];
+        // 不正なエスケープがあっても文字列の終わりまでは読み、最初のエラーを返す
+        let mut error = None;
+        while self.current != '"' {
+            match self.current {
+                // 閉じられないままEOFに達した文字列は不正なtokenとする
+                '\u{0000}' => return self.unterminated("string"),
+                '$' if self.next == '{' => {
+                    self.read_char();
+                    match self.read_interpolation() {
+                        Ok((code, position)) => {
+                            if !s.is_empty() {
+                                parts.push(StringPart::Literal(std::mem::take(&mut s)));
+                            }
+                            parts.push(StringPart::Code(code, position));
+                        }
+                        Err(e) => return e,
+                    }
+                }
+                '\\' => {
+                    self.read_char();
+                    match self.read_escape() {
+                        Ok(c) => s.push(c),
+                        Err(e) => {
+                            error.get_or_insert(e);
+                        }
+                    }
+                }
+                c => s.push(c),
+            }
+            self.read_char();
+        }
+        self.read_char();
+        if let Some(e) = error {
+            return Token::ILLEGAL(e);
+        }
+        if parts.is_empty() {
+            return Token::STRING(s);
+        }
+        if !s.is_empty() {
+            parts.push(StringPart::Literal(s));
+        }
+        Token::INTERPOLATED(parts)
+    }
+
+    // currentが`${`の`{`の状態で呼ぶ。対応する`}`まで読み、中身のソースとその開始位置を返す
+    // 中身の文字列リテラルに含まれる`{`や`}`は数えない
+    fn read_interpolation(&mut self) -> Result<(String, Position), Token> {
+        self.read_char();
+        let start = self.position;
+        let mut code = String::new();
+        let mut depth = 0;
+        let mut in_string = false;
+        loop {
+            match self.current {
+                '\u{0000}' => return Err(self.unterminated("interpolation")),
+                '}' if !in_string && depth == 0 => return Ok((code, start)),
+                '{' if !in_string => depth += 1,
+                '}' if !in_string => depth -= 1,
+                '"' => in_string = !in_string,
+                // エスケープされた文字は次の文字ごと読む
+                '\\' if in_string => {
+                    code.push(self.current);
+                    self.read_char();
+                }
+                _ => {}
+            }
+            code.push(self.current);
+            self.read_char();
+        }
+    }
+
+    // currentが`\`の次の文字の状態で呼ぶ。エスケープの最後の文字で止まる
+    fn read_escape(&mut self) -> Result<char, String> {
+        match self.current {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            // `${`をそのまま書きたいときは`\${`
+            '$' => Ok('$'),
+            // \u{1F600}
+            'u' => {
+                if self.next != '{' {
+                    return Err("invalid unicode escape".to_string());
+                }
+                self.read_char();
+                let mut hex = String::new();
+                while self.next != '}' {
+                    if !self.next.is_ascii_hexdigit() {
+                        return Err("invalid unicode escape".to_string());
+                    }
+                    self.read_char();
+                    hex.push(self.current);
+                }
+                self.read_char();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid unicode escape `\\u{{{}}}`", hex))
+            }
+            '\u{0000}' => Err("unterminated string".to_string()),
+            c => Err(format!("unknown escape sequence `\\{}`", c)),
+        }
+    }
+
+    fn read_raw_string(&mut self) -> Token {
+        self.read_char();
+        let mut s = String::new();
+        while self.current != '"' {
+            if self.current == '\u{0000}' {
+                return self.unterminated("string");
+            }
+            s.push(self.current);
+            self.read_char();
+        }
+        self.read_char();
+        Token::STRING(s)
+    }
+
     fn token_from(&self, ident: &str) -> Token {
         match ident {
             "let" => Token::LET,
@@ -86,12 +322,40 @@ impl<'a> Lexer<'a> {
             "return" => Token::RETURN,
             "for" => Token::FOR,
             "in" => Token::IN,
+            "while" => Token::WHILE,
+            "break" => Token::BREAK,
+            "continue" => Token::CONTINUE,
+            "null" => Token::NULL,
+            "delete" => Token::DELETE,
             ident => Token::IDENT(ident.to_string()),
         }
     }
 
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+        self.next_spanned_token().0
+    }
+
+    // tokenと、そのtokenがソース上で占める範囲を返す
+    pub fn next_spanned_token(&mut self) -> (Token, Span) {
+        let start = self.position;
+        if let Some(illegal) = self.skip_whitespace() {
+            // 前のtokenのdocコメントが残らないようにする
+            self.doc = None;
+            self.docs.clear();
+            return (illegal, Span::new(start, self.position));
+        }
+        let start = self.position;
+        let token = self.read_token();
+        self.doc = if self.docs.is_empty() {
+            None
+        } else {
+            Some(self.docs.join("\n"))
+        };
+        self.docs.clear();
+        (token, Span::new(start, self.position))
+    }
+
+    fn read_token(&mut self) -> Token {
         let token = match self.current {
             '=' => match self.next {
                 // ==の分岐
@@ -109,12 +373,80 @@ impl<'a> Lexer<'a> {
                 }
                 _ => Token::BANG,
             },
-            '+' => Token::PLUS,
-            '-' => Token::MINUS,
-            '*' => Token::ASTERISK,
-            '/' => Token::SLASH,
-            '<' => Token::LT,
-            '>' => Token::GT,
+            // &と|は単体では使わないので、2文字揃っていなければ不正な文字とする
+            '&' => match self.next {
+                '&' => {
+                    self.read_char();
+                    Token::AND
+                }
+                _ => Token::ILLEGAL("unexpected character `&`".to_string()),
+            },
+            '|' => match self.next {
+                '|' => {
+                    self.read_char();
+                    Token::OR
+                }
+                _ => Token::ILLEGAL("unexpected character `|`".to_string()),
+            },
+            '+' => match self.next {
+                '=' => {
+                    self.read_char();
+                    Token::PLUSEQ
+                }
+                _ => Token::PLUS,
+            },
+            '-' => match self.next {
+                '=' => {
+                    self.read_char();
+                    Token::MINUSEQ
+                }
+                _ => Token::MINUS,
+            },
+            '*' => match self.next {
+                '*' => {
+                    self.read_char();
+                    Token::POW
+                }
+                '=' => {
+                    self.read_char();
+                    Token::ASTERISKEQ
+                }
+                _ => Token::ASTERISK,
+            },
+            '/' => match self.next {
+                '=' => {
+                    self.read_char();
+                    Token::SLASHEQ
+                }
+                _ => Token::SLASH,
+            },
+            '%' => Token::PERCENT,
+            '.' => match (self.next, self.peek_second()) {
+                ('.', '=') => {
+                    self.read_char();
+                    self.read_char();
+                    Token::DOTDOTEQ
+                }
+                ('.', _) => {
+                    self.read_char();
+                    Token::DOTDOT
+                }
+                _ => Token::ILLEGAL("unexpected character `.`".to_string()),
+            },
+            '<' => match self.next {
+                '=' => {
+                    self.read_char();
+                    Token::LTEQ
+                }
+                _ => Token::LT,
+            },
+            '>' => match self.next {
+                '=' => {
+                    self.read_char();
+                    Token::GTEQ
+                }
+                _ => Token::GT,
+            },
             ',' => Token::COMMA,
             ':' => Token::COLON,
             ';' => Token::SEMICOLON,
@@ -124,16 +456,13 @@ impl<'a> Lexer<'a> {
             '}' => Token::RBRACE,
             '[' => Token::LBRACKET,
             ']' => Token::RBRACKET,
-            '"' => {
+            '"' => return self.read_string(),
+            '\u{0000}' => Token::EOF,
+            // r"..."はエスケープを解釈しない
+            'r' if self.next == '"' => {
                 self.read_char();
-                let mut ident = String::new();
-                while self.current != '"' {
-                    ident.push(self.current);
-                    self.read_char();
-                }
-                Token::STRING(ident)
+                return self.read_raw_string();
             }
-            '\u{0000}' => Token::EOF,
             c => {
                 if Self::is_letter(c) {
                     let ident = self.read_identifier();
@@ -141,7 +470,7 @@ impl<'a> Lexer<'a> {
                 } else if Self::is_digit(c) {
                     return self.read_number();
                 } else {
-                    return Token::ILLEGAL;
+                    Token::ILLEGAL(format!("unexpected character `{}`", c))
                 }
             }
         };
@@ -155,7 +484,8 @@ mod tests {
     use super::*;
     #[test]
     fn test_lexing() {
-        let input = "=+(){},;!-/*<>";
+        // `/*`はコメントの始まりになるので空白で区切る
+        let input = "=+(){},;!-/ *<>";
         let mut lexer = Lexer::new(input);
         let tests = vec![
             Token::ASSIGN,
@@ -179,6 +509,274 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_illegal() {
+        let tests = vec![
+            (
+                "@1",
+                vec![
+                    Token::ILLEGAL("unexpected character `@`".to_string()),
+                    Token::INT(1),
+                ],
+            ),
+            (
+                "\"abc",
+                vec![Token::ILLEGAL("unterminated string".to_string())],
+            ),
+            (
+                "1.",
+                vec![
+                    Token::INT(1),
+                    Token::ILLEGAL("unexpected character `.`".to_string()),
+                ],
+            ),
+            (
+                "1 /* a /* b */",
+                vec![
+                    Token::INT(1),
+                    Token::ILLEGAL("unterminated block comment".to_string()),
+                ],
+            ),
+            (
+                "a & b",
+                vec![
+                    Token::IDENT("a".to_string()),
+                    Token::ILLEGAL("unexpected character `&`".to_string()),
+                    Token::IDENT("b".to_string()),
+                ],
+            ),
+            (
+                "4294967296 99999999999999999999",
+                vec![
+                    Token::INT(4294967296),
+                    Token::ILLEGAL(
+                        "integer literal `99999999999999999999` is too large".to_string(),
+                    ),
+                ],
+            ),
+        ];
+        for (input, expects) in tests {
+            let mut lexer = Lexer::new(input);
+            for t in expects {
+                assert_eq!(lexer.next_token(), t);
+            }
+            assert_eq!(lexer.next_token(), Token::EOF);
+        }
+    }
+
+    #[test]
+    fn test_string() {
+        let tests = vec![
+            (r#""a\nb""#, Token::STRING("a\nb".to_string())),
+            (r#""tab\there""#, Token::STRING("tab\there".to_string())),
+            (r#""say \"hi\"""#, Token::STRING("say \"hi\"".to_string())),
+            (r#""back\\slash""#, Token::STRING("back\\slash".to_string())),
+            (r#""\u{1F600}\u{3042}""#, Token::STRING("😀あ".to_string())),
+            (
+                "\"line1\nline2\"",
+                Token::STRING("line1\nline2".to_string()),
+            ),
+            (r#"r"C:\path\n""#, Token::STRING("C:\\path\\n".to_string())),
+            (
+                r#""a\qb""#,
+                Token::ILLEGAL("unknown escape sequence `\\q`".to_string()),
+            ),
+            (
+                r#""\u{110000}""#,
+                Token::ILLEGAL("invalid unicode escape `\\u{110000}`".to_string()),
+            ),
+            (
+                r#""\u1234""#,
+                Token::ILLEGAL("invalid unicode escape".to_string()),
+            ),
+            (
+                r#""abc\""#,
+                Token::ILLEGAL("unterminated string".to_string()),
+            ),
+            (
+                r#"r"abc"#,
+                Token::ILLEGAL("unterminated string".to_string()),
+            ),
+        ];
+        for (input, expect) in tests {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(lexer.next_token(), expect, "input: {}", input);
+            assert_eq!(lexer.next_token(), Token::EOF, "input: {}", input);
+        }
+        // rの後に"が無ければただの識別子
+        let mut lexer = Lexer::new("r + rr");
+        assert_eq!(lexer.next_token(), Token::IDENT("r".to_string()));
+        assert_eq!(lexer.next_token(), Token::PLUS);
+        assert_eq!(lexer.next_token(), Token::IDENT("rr".to_string()));
+    }
+
+    #[test]
+    fn test_interpolated_string() {
+        let input = r#""Hello, ${name}! ${len(m["}"])} \${x}" "${a}""#;
+        let mut lexer = Lexer::new(input);
+        assert_eq!(
+            lexer.next_token(),
+            Token::INTERPOLATED(vec![
+                StringPart::Literal("Hello, ".to_string()),
+                StringPart::Code(
+                    "name".to_string(),
+                    Position {
+                        line: 1,
+                        column: 11,
+                        offset: 10
+                    }
+                ),
+                StringPart::Literal("! ".to_string()),
+                StringPart::Code(
+                    r#"len(m["}"])"#.to_string(),
+                    Position {
+                        line: 1,
+                        column: 20,
+                        offset: 19
+                    }
+                ),
+                StringPart::Literal(" ${x}".to_string()),
+            ])
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Token::INTERPOLATED(vec![StringPart::Code(
+                "a".to_string(),
+                Position {
+                    line: 1,
+                    column: 43,
+                    offset: 42
+                }
+            )])
+        );
+        assert_eq!(lexer.next_token(), Token::EOF);
+
+        let mut lexer = Lexer::new(r#""a ${b""#);
+        assert_eq!(
+            lexer.next_token(),
+            Token::ILLEGAL("unterminated interpolation".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unterminated() {
+        let tests = vec![
+            ("\"abc", true),
+            ("r\"abc", true),
+            ("\"a ${b", true),
+            ("1 /* a", true),
+            ("\"abc\"", false),
+            ("@", false),
+            (r#""\q""#, false),
+        ];
+        for (input, expect) in tests {
+            let mut lexer = Lexer::new(input);
+            while lexer.next_token() != Token::EOF {}
+            assert_eq!(lexer.is_unterminated(), expect, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_comment() {
+        let input = r#"
+            // line comment
+            let a = 1; // trailing
+            /* block
+               /* nested */ still comment */
+            a /* inline */ + 2
+            //// not a doc
+            /// doc line 1
+            ///doc line 2
+            let b = 3;
+            // end"#;
+        let mut lexer = Lexer::new(input);
+        let tests = vec![
+            (Token::LET, None),
+            (Token::IDENT("a".to_string()), None),
+            (Token::ASSIGN, None),
+            (Token::INT(1), None),
+            (Token::SEMICOLON, None),
+            (Token::IDENT("a".to_string()), None),
+            (Token::PLUS, None),
+            (Token::INT(2), None),
+            (Token::LET, Some("doc line 1\ndoc line 2")),
+            (Token::IDENT("b".to_string()), None),
+            (Token::ASSIGN, None),
+            (Token::INT(3), None),
+            (Token::SEMICOLON, None),
+            (Token::EOF, None),
+        ];
+        for (t, doc) in tests {
+            assert_eq!(lexer.next_token(), t);
+            assert_eq!(lexer.doc().map(|d| d.as_str()), doc);
+        }
+
+        // 閉じられていないブロックコメントにはdocコメントを付けない
+        let mut lexer = Lexer::new("/// doc\nlet /// doc2\n/* a");
+        assert_eq!(lexer.next_token(), Token::LET);
+        assert_eq!(lexer.doc().map(|d| d.as_str()), Some("doc"));
+        assert_eq!(
+            lexer.next_token(),
+            Token::ILLEGAL("unterminated block comment".to_string())
+        );
+        assert_eq!(lexer.doc(), None);
+        assert_eq!(lexer.next_token(), Token::EOF);
+        assert_eq!(lexer.doc(), None);
+    }
+
+    #[test]
+    fn test_identifier() {
+        let input = "x1 user2_id _tmp 名前 café 1abc ÿ2";
+        let mut lexer = Lexer::new(input);
+        let tests = vec![
+            Token::IDENT("x1".to_string()),
+            Token::IDENT("user2_id".to_string()),
+            Token::IDENT("_tmp".to_string()),
+            Token::IDENT("名前".to_string()),
+            Token::IDENT("café".to_string()),
+            // 数字から始まるものは数値と識別子に分かれる
+            Token::INT(1),
+            Token::IDENT("abc".to_string()),
+            Token::IDENT("ÿ2".to_string()),
+            Token::EOF,
+        ];
+        for t in tests {
+            assert_eq!(lexer.next_token(), t);
+        }
+        let mut lexer = Lexer::new("a😀");
+        assert_eq!(lexer.next_token(), Token::IDENT("a".to_string()));
+        assert_eq!(
+            lexer.next_token(),
+            Token::ILLEGAL("unexpected character `😀`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_span() {
+        let input = "let abc = 10;\n  \"あい\" + x";
+        let mut lexer = Lexer::new(input);
+        let tests = vec![
+            (Token::LET, (1, 1, 0), (1, 4, 3)),
+            (Token::IDENT("abc".to_string()), (1, 5, 4), (1, 8, 7)),
+            (Token::ASSIGN, (1, 9, 8), (1, 10, 9)),
+            (Token::INT(10), (1, 11, 10), (1, 13, 12)),
+            (Token::SEMICOLON, (1, 13, 12), (1, 14, 13)),
+            (Token::STRING("あい".to_string()), (2, 3, 16), (2, 7, 24)),
+            (Token::PLUS, (2, 8, 25), (2, 9, 26)),
+            (Token::IDENT("x".to_string()), (2, 10, 27), (2, 11, 28)),
+            (Token::EOF, (2, 11, 28), (2, 11, 28)),
+        ];
+        for (t, start, end) in tests {
+            let (token, span) = lexer.next_spanned_token();
+            assert_eq!(token, t);
+            assert_eq!(
+                (span.start.line, span.start.column, span.start.offset),
+                start
+            );
+            assert_eq!((span.end.line, span.end.column, span.end.offset), end);
+        }
+    }
+
     #[test]
     fn test_next_token() {
         let input = r#"
@@ -202,6 +800,10 @@ mod tests {
             5.5;
             for 
             in
+            && ||
+            <= >= % ** += -= *= /=
+            while break continue null delete
+            0..10 1..=n 1.5..2
             "#;
 
         let mut lexer = Lexer::new(input);
@@ -258,6 +860,30 @@ mod tests {
             Token::SEMICOLON,
             Token::FOR,
             Token::IN,
+            Token::AND,
+            Token::OR,
+            Token::LTEQ,
+            Token::GTEQ,
+            Token::PERCENT,
+            Token::POW,
+            Token::PLUSEQ,
+            Token::MINUSEQ,
+            Token::ASTERISKEQ,
+            Token::SLASHEQ,
+            Token::WHILE,
+            Token::BREAK,
+            Token::CONTINUE,
+            Token::NULL,
+            Token::DELETE,
+            Token::INT(0),
+            Token::DOTDOT,
+            Token::INT(10),
+            Token::INT(1),
+            Token::DOTDOTEQ,
+            Token::IDENT("n".to_string()),
+            Token::FLOAT(1.5),
+            Token::DOTDOT,
+            Token::INT(2),
             Token::EOF,
         ];
         for t in tests {
@@ -0,0 +1,17 @@
+pub mod ast;
+mod builtins;
+pub mod diagnostic;
+pub mod environment;
+pub mod error;
+pub mod evaluator;
+pub mod interpreter;
+pub mod lexer;
+pub mod object;
+pub mod parser;
+pub mod span;
+pub mod token;
+
+pub use diagnostic::Diagnostic;
+pub use error::Error;
+pub use interpreter::Interpreter;
+pub use object::Object;
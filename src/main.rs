@@ -1,16 +1,14 @@
-use std::io::stdin;
-mod ast;
-mod builtins;
-mod environment;
-mod error;
-mod evaluator;
-mod lexer;
-mod object;
-mod parser;
+use std::env;
+use std::process;
 mod repl;
-mod token;
+mod script;
 use repl::start;
 
 fn main() {
-    start(stdin());
+    let args: Vec<String> = env::args().collect();
+    // 引数でファイルが渡されたらスクリプトとして実行し、なければREPLを起動する
+    match args.get(1) {
+        Some(path) => process::exit(script::run(path, args[2..].to_vec())),
+        None => start(),
+    }
 }
@@ -1,16 +1,20 @@
 use crate::ast::{Expression, Statement};
 use crate::environment::Environment;
 use crate::error::Error;
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::fmt;
 
 // Objectの中でKeyとして使えるものを抽出する
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum MapKey {
-    Integer(i32),
+    Integer(i64),
     Boolean(bool),
     String(String),
     Null,
+    // hashできる値だけを要素に持つ配列。複数の値を組にしたkeyとして使う
+    Array(Vec<MapKey>),
 }
 
 impl fmt::Display for MapKey {
@@ -20,25 +24,60 @@ impl fmt::Display for MapKey {
             Self::Boolean(b) => write!(f, "{}", b),
             Self::String(s) => write!(f, "{}", s),
             Self::Null => write!(f, "null"),
+            Self::Array(keys) => write!(
+                f,
+                "[{}]",
+                keys.iter()
+                    .map(|k| format!("{}", k))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
         }
     }
 }
 
-impl From<Object> for MapKey {
-    fn from(item: Object) -> Self {
+// 小数、連想配列、関数などはkeyにできない
+impl TryFrom<Object> for MapKey {
+    type Error = Error;
+
+    fn try_from(item: Object) -> Result<Self, Self::Error> {
         match item {
-            Object::Integer(i) => MapKey::Integer(i),
-            Object::String(s) => MapKey::String(s),
-            Object::Boolean(b) => MapKey::Boolean(b),
-            Object::Null => MapKey::Null,
-            _ => MapKey::Null,
+            Object::Integer(i) => Ok(MapKey::Integer(i)),
+            Object::String(s) => Ok(MapKey::String(s)),
+            Object::Boolean(b) => Ok(MapKey::Boolean(b)),
+            Object::Null => Ok(MapKey::Null),
+            Object::Array(elements) => Ok(MapKey::Array(
+                elements
+                    .into_iter()
+                    .map(MapKey::try_from)
+                    .collect::<Result<_, _>>()?,
+            )),
+            o => Err(Error::EvalError {
+                msg: format!("{} can not be used as a map key", o.type_name()),
+                span: None,
+                help: None,
+            }),
+        }
+    }
+}
+
+impl From<MapKey> for Object {
+    fn from(key: MapKey) -> Self {
+        match key {
+            MapKey::Integer(i) => Object::Integer(i),
+            MapKey::String(s) => Object::String(s),
+            MapKey::Boolean(b) => Object::Boolean(b),
+            MapKey::Null => Object::Null,
+            MapKey::Array(keys) => Object::Array(keys.into_iter().map(Object::from).collect()),
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(unpredictable_function_pointer_comparisons)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Object {
-    Integer(i32),
+    Integer(i64),
+    Float(f64),
     Boolean(bool),
     String(String),
     Return(Box<Object>),
@@ -49,22 +88,127 @@ pub enum Object {
     },
     Array(Vec<Object>),
     Builtin(fn(Vec<Object>) -> Result<Object, Error>),
-    Map(HashMap<MapKey, Box<Object>>),
+    // 表示や繰り返しは追加した順になる
+    Map(IndexMap<MapKey, Box<Object>>),
+    // startからendの手前までの整数。配列と違い要素は必要になるまで作らない
+    // `..=`で作ったものもendは1つ後ろにずらして持ち、inclusiveは表示にだけ使う
+    Range {
+        start: i64,
+        end: i64,
+        inclusive: bool,
+    },
     Null,
 }
 
+impl Object {
+    // エラーメッセージなどで使う型の名前
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Integer(_) => "integer",
+            Self::Float(_) => "float",
+            Self::Boolean(_) => "boolean",
+            Self::String(_) => "string",
+            Self::Return(v) => v.type_name(),
+            Self::Function { .. } => "function",
+            Self::Array(_) => "array",
+            Self::Builtin(_) => "builtin function",
+            Self::Map(_) => "map",
+            Self::Range { .. } => "range",
+            Self::Null => "null",
+        }
+    }
+
+    // 条件として使ったときに真とみなすか
+    // null, false, 0, 0.0, NaN, "", [], {}, 空のrangeは偽で、それ以外は全て真
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Null => false,
+            Self::Boolean(b) => *b,
+            Self::Integer(i) => *i != 0,
+            Self::Float(f) => *f != 0.0 && !f.is_nan(),
+            Self::String(s) => !s.is_empty(),
+            Self::Array(arr) => !arr.is_empty(),
+            Self::Map(m) => !m.is_empty(),
+            Self::Range { start, end, .. } => start < end,
+            Self::Return(v) => v.is_truthy(),
+            Self::Function { .. } | Self::Builtin(_) => true,
+        }
+    }
+
+    // `==`で使う比較。配列と連想配列は中身まで比べる
+    // 型が違う場合はエラーにせずfalseにする。ただし整数と小数は値で比べる
+    pub fn structural_eq(&self, other: &Object) -> bool {
+        match (self, other) {
+            (Self::Return(l), r) => l.structural_eq(r),
+            (l, Self::Return(r)) => l.structural_eq(r),
+            // 書き方が違っても同じ整数を含むなら等しい
+            (
+                Self::Range { start, end, .. },
+                Self::Range {
+                    start: s, end: e, ..
+                },
+            ) => start == s && end == e,
+            (Self::Integer(l), Self::Float(r)) => (*l as f64) == *r,
+            (Self::Float(l), Self::Integer(r)) => *l == (*r as f64),
+            (Self::Array(l), Self::Array(r)) => {
+                l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.structural_eq(r))
+            }
+            (Self::Map(l), Self::Map(r)) => {
+                l.len() == r.len()
+                    && l.iter()
+                        .all(|(k, v)| r.get(k).is_some_and(|rv| v.structural_eq(rv)))
+            }
+            (l, r) => l == r,
+        }
+    }
+
+    // `<`, `>`などで使う比較。数値同士、文字列同士、配列同士だけ比べられる
+    // 配列は先頭の要素から順に比べる。比べられない組み合わせはNone
+    pub fn compare(&self, other: &Object) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Integer(l), Self::Integer(r)) => Some(l.cmp(r)),
+            (Self::Integer(l), Self::Float(r)) => (*l as f64).partial_cmp(r),
+            (Self::Float(l), Self::Integer(r)) => l.partial_cmp(&(*r as f64)),
+            (Self::Float(l), Self::Float(r)) => l.partial_cmp(r),
+            (Self::String(l), Self::String(r)) => Some(l.cmp(r)),
+            (Self::Array(l), Self::Array(r)) => {
+                for (l, r) in l.iter().zip(r) {
+                    match l.compare(r)? {
+                        Ordering::Equal => continue,
+                        o => return Some(o),
+                    }
+                }
+                Some(l.len().cmp(&r.len()))
+            }
+            _ => None,
+        }
+    }
+}
+
+// rangeに含まれる整数の個数。endがstart以下なら空
+// 個数がi64に収まらない場合はNone
+pub fn range_len(start: i64, end: i64) -> Option<i64> {
+    if end <= start {
+        return Some(0);
+    }
+    end.checked_sub(start)
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Integer(i) => write!(f, "{}", i.to_string()),
+            Self::Integer(i) => write!(f, "{}", i),
+            // 整数と区別できるよう、小数部が無くても`1.0`のように表示する
+            Self::Float(x) if x.is_finite() && x.fract() == 0.0 => write!(f, "{:.1}", x),
+            Self::Float(x) => write!(f, "{}", x),
             Self::String(s) => write!(f, "{}", s),
-            Self::Boolean(b) => write!(f, "{}", b.to_string()),
+            Self::Boolean(b) => write!(f, "{}", b),
             Self::Return(v) => write!(f, "{}", v.as_ref()),
             Self::Null => write!(f, "null"),
             Self::Function { .. } => write!(f, ""),
             Self::Array(elements) => {
                 let mut s = String::from("");
-                s.push_str("[");
+                s.push('[');
                 s.push_str(
                     &elements
                         .iter()
@@ -72,22 +216,28 @@ impl fmt::Display for Object {
                         .collect::<Vec<_>>()
                         .join(","),
                 );
-                s.push_str("]");
+                s.push(']');
                 write!(f, "{}", s)
             }
             Self::Map(m) => {
                 let mut s = String::from("");
-                s.push_str("{");
+                s.push('{');
                 s.push_str(
                     &m.iter()
                         .map(|(k, v)| format!("{}: {}", k, v))
                         .collect::<Vec<_>>()
                         .join(","),
                 );
-                s.push_str("}");
+                s.push('}');
                 write!(f, "{}", s)
             }
             Self::Builtin(_) => write!(f, "builtin function"),
+            Self::Range {
+                start,
+                end,
+                inclusive: true,
+            } => write!(f, "{}..={}", start, end - 1),
+            Self::Range { start, end, .. } => write!(f, "{}..{}", start, end),
         }
     }
 }
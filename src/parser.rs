@@ -2,17 +2,21 @@ use crate::ast;
 use crate::error::Error;
 use crate::error::Error::ParseError;
 use crate::lexer::Lexer;
-use crate::token::Token;
-use std::collections::BTreeMap;
+use crate::span::Span;
+use crate::token::{StringPart, Token};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd)]
 enum Precedence {
     Lowest,
+    Or,          // ||
+    And,         // &&
     Equals,      // ==
     Lessgreater, // > <
+    Range,       // .. ..=
     Sum,         // + -
-    Product,     // * /
+    Product,     // * / %
     Prefix,      // -X or !X
+    Power,       // ** -2 ** 2 は -(2 ** 2)
     Call,        // myFunction(X)
     Index,       // array[index]
 }
@@ -20,10 +24,14 @@ enum Precedence {
 impl Precedence {
     fn from_token(kind: &Token) -> Self {
         match kind {
+            Token::OR => Self::Or,
+            Token::AND => Self::And,
             Token::EQ | Token::NEQ => Self::Equals,
-            Token::LT | Token::GT => Self::Lessgreater,
+            Token::LT | Token::GT | Token::LTEQ | Token::GTEQ => Self::Lessgreater,
+            Token::DOTDOT | Token::DOTDOTEQ => Self::Range,
             Token::PLUS | Token::MINUS => Self::Sum,
-            Token::SLASH | Token::ASTERISK => Self::Product,
+            Token::SLASH | Token::ASTERISK | Token::PERCENT => Self::Product,
+            Token::POW => Self::Power,
             Token::LPAREN => Self::Call,
             Token::LBRACKET => Self::Index,
             _ => Self::Lowest,
@@ -36,14 +44,26 @@ pub struct Parser<'a> {
     lexer: &'a mut Lexer<'a>,
     current_token: Token,
     peek_token: Token,
+    current_span: Span,
+    peek_span: Span,
+    // 各tokenの直前にあった`///`コメント
+    current_doc: Option<String>,
+    peek_doc: Option<String>,
+    // 途中で見つかったエラー。エラーがあっても最後までparseを続ける
+    errors: Vec<Error>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(lexer: &'a mut Lexer<'a>) -> Parser<'a> {
         let mut p = Parser {
             lexer,
-            current_token: Token::ILLEGAL,
-            peek_token: Token::ILLEGAL,
+            current_token: Token::EOF,
+            peek_token: Token::EOF,
+            current_span: Span::default(),
+            peek_span: Span::default(),
+            current_doc: None,
+            peek_doc: None,
+            errors: vec![],
         };
         p.next_token();
         p.next_token();
@@ -52,7 +72,12 @@ impl<'a> Parser<'a> {
 
     fn next_token(&mut self) {
         self.current_token = self.peek_token.clone();
-        self.peek_token = self.lexer.next_token();
+        self.current_span = self.peek_span;
+        self.current_doc = self.peek_doc.take();
+        let (token, span) = self.lexer.next_spanned_token();
+        self.peek_token = token;
+        self.peek_span = span;
+        self.peek_doc = self.lexer.doc().cloned();
     }
 
     fn current_token_is(&self, kind: Token) -> bool {
@@ -72,22 +97,26 @@ impl<'a> Parser<'a> {
             self.next_token();
             return true;
         }
-        return false;
+        false
     }
 
     pub fn parse_let_statement(&mut self) -> Result<ast::Statement, Error> {
+        let doc = self.current_doc.clone();
+        let start = self.current_span;
         self.next_token();
         let identifier = match self.current_token.clone() {
             Token::IDENT(ident) => ident,
             _ => {
                 return Err(ParseError {
-                    msg: format!("expect `IDENT` but got `{}`", self.peek_token),
+                    msg: format!("expect `IDENT` but got `{}`", self.current_token),
+                    span: Some(self.current_span),
                 })
             }
         };
         if !self.expect_peek(Token::ASSIGN) {
             return Err(ParseError {
-                msg: format!("expect `IDENT` but got `{}`", self.peek_token),
+                msg: format!("expect `ASSIGN` but got `{}`", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
         // assignの次のtoken。右辺の始まり
@@ -99,68 +128,118 @@ impl<'a> Parser<'a> {
         Ok(ast::Statement::Let {
             name: identifier,
             value,
+            doc,
+            span: start.to(self.current_span),
         })
     }
 
     pub fn parse_return_statement(&mut self) -> Result<ast::Statement, Error> {
+        let start = self.current_span;
         self.next_token();
         let return_value = self.parse_expression(Precedence::Lowest)?;
         if self.peek_token_is(Token::SEMICOLON) {
             self.next_token();
         }
-        Ok(ast::Statement::Return(return_value))
+        Ok(ast::Statement::Return {
+            value: return_value,
+            span: start.to(self.current_span),
+        })
     }
 
     fn parse_call_expression(
         &mut self,
         function: ast::Expression,
+        start: Span,
     ) -> Result<ast::Expression, Error> {
+        let arguments = self.parse_expressions(Token::RPAREN)?;
         Ok(ast::Expression::Call {
             function: Box::new(function),
-            arguments: self.parse_expressions(Token::RPAREN)?,
+            arguments,
+            span: start.to(self.current_span),
         })
     }
 
-    fn parse_index_expression(&mut self, left: ast::Expression) -> Result<ast::Expression, Error> {
+    fn parse_index_expression(
+        &mut self,
+        left: ast::Expression,
+        start: Span,
+    ) -> Result<ast::Expression, Error> {
         self.next_token();
         let index = self.parse_expression(Precedence::Lowest)?;
         if !self.expect_peek(Token::RBRACKET) {
             return Err(ParseError {
-                msg: format!("Expect ], but got {:?}", self.peek_token),
+                msg: format!("Expect ], but got {}", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
-        Ok(ast::Expression::Index {
+        let expression = ast::Expression::Index {
             left: Box::new(left),
             index: Box::new(index),
-        })
+            span: start.to(self.current_span),
+        };
+        // arr[0] = 1 のような要素への代入
+        if self.peek_token_is_assign() {
+            self.next_token();
+            return self.parse_infix(expression, start);
+        }
+        Ok(expression)
+    }
+
+    fn peek_token_is_assign(&self) -> bool {
+        matches!(
+            self.peek_token,
+            Token::ASSIGN | Token::PLUSEQ | Token::MINUSEQ | Token::ASTERISKEQ | Token::SLASHEQ
+        )
     }
 
-    fn parse_infix(&mut self, left: ast::Expression) -> Result<ast::Expression, Error> {
+    // startは左辺の始まりの位置
+    fn parse_infix(
+        &mut self,
+        left: ast::Expression,
+        start: Span,
+    ) -> Result<ast::Expression, Error> {
         let operator = match &self.current_token {
             Token::PLUS => ast::InfixOprator::Plus,
             Token::MINUS => ast::InfixOprator::Minus,
             Token::ASTERISK => ast::InfixOprator::Asterisk,
             Token::SLASH => ast::InfixOprator::Slash,
+            Token::PERCENT => ast::InfixOprator::Percent,
+            Token::POW => ast::InfixOprator::Pow,
             Token::GT => ast::InfixOprator::Gt,
             Token::LT => ast::InfixOprator::Lt,
+            Token::GTEQ => ast::InfixOprator::GtEq,
+            Token::LTEQ => ast::InfixOprator::LtEq,
+            Token::DOTDOT => ast::InfixOprator::Range,
+            Token::DOTDOTEQ => ast::InfixOprator::RangeInclusive,
             Token::ASSIGN => ast::InfixOprator::Assign,
+            Token::PLUSEQ => ast::InfixOprator::PlusAssign,
+            Token::MINUSEQ => ast::InfixOprator::MinusAssign,
+            Token::ASTERISKEQ => ast::InfixOprator::AsteriskAssign,
+            Token::SLASHEQ => ast::InfixOprator::SlashAssign,
             Token::EQ => ast::InfixOprator::Equal,
             Token::NEQ => ast::InfixOprator::Nequal,
+            Token::AND => ast::InfixOprator::And,
+            Token::OR => ast::InfixOprator::Or,
             Token::LPAREN => {
-                return self.parse_call_expression(left);
+                return self.parse_call_expression(left, start);
             }
             Token::LBRACKET => {
-                return self.parse_index_expression(left);
+                return self.parse_index_expression(left, start);
             }
             _ => return Ok(left),
         };
-        let precedence = Precedence::from_token(&self.current_token);
+        let precedence = match self.current_token {
+            // **は右結合なので、右辺は一つ低い優先順位で読んで続く**も取り込む
+            Token::POW => Precedence::Prefix,
+            _ => Precedence::from_token(&self.current_token),
+        };
         self.next_token();
         let right = self.parse_expression(precedence)?;
         Ok(ast::Expression::Infix {
             left: Box::new(left),
             operator,
             right: Box::new(right),
+            span: start.to(self.current_span),
         })
     }
 
@@ -171,6 +250,7 @@ impl<'a> Parser<'a> {
         if !self.expect_peek(Token::RPAREN) {
             return Err(ParseError {
                 msg: "Parentheses are not closed. in if expression".to_string(),
+                span: Some(self.peek_span),
             });
         }
         Ok(expression)
@@ -180,30 +260,42 @@ impl<'a> Parser<'a> {
         self.next_token();
         let mut statements = vec![];
         while !self.current_token_is(Token::RBRACE) && !self.current_token_is(Token::EOF) {
-            let stmt = self.parse_statement()?;
-            statements.push(stmt);
+            match self.parse_statement() {
+                Ok(stmt) => statements.push(stmt),
+                Err(e) => {
+                    self.errors.push(e);
+                    // blockを閉じる`}`で止まった場合は読み進めずにblockを終える
+                    if self.synchronize() {
+                        break;
+                    }
+                }
+            }
             self.next_token();
         }
         Ok(ast::Statement::Block(statements))
     }
 
     fn parse_if_expression(&mut self) -> Result<ast::Expression, Error> {
+        let start = self.current_span;
         if !self.expect_peek(Token::LPAREN) {
             return Err(ParseError {
-                msg: format!("Unexpected token {:?}. wanted LPAREN", self.peek_token),
+                msg: format!("Unexpected token {}. wanted LPAREN", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
         self.next_token();
         let condition = self.parse_expression(Precedence::Lowest)?;
         if !self.expect_peek(Token::RPAREN) {
             return Err(ParseError {
-                msg: format!("Parentheses are not closed. in if expression"),
+                msg: "Parentheses are not closed. in if expression".to_string(),
+                span: Some(self.peek_span),
             });
         }
         //if文の中身
         if !self.expect_peek(Token::LBRACE) {
             return Err(ParseError {
-                msg: format!("Unexpected token {:?}. wanted `{{`", self.peek_token),
+                msg: format!("Unexpected token {}. wanted `{{`", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
         let consequence = self.parse_block_statement()?;
@@ -211,7 +303,8 @@ impl<'a> Parser<'a> {
         let alternative = if self.expect_peek(Token::ELSE) {
             if !self.expect_peek(Token::LBRACE) {
                 return Err(ParseError {
-                    msg: format!("Unexpected token {:?}. wanted `{{`", self.peek_token),
+                    msg: format!("Unexpected token {}. wanted `{{`", self.peek_token),
+                    span: Some(self.peek_span),
                 });
             }
             let stmt = self.parse_block_statement()?;
@@ -223,6 +316,83 @@ impl<'a> Parser<'a> {
             condition: Box::new(condition),
             consequence: Box::new(consequence),
             alternative,
+            span: start.to(self.current_span),
+        })
+    }
+
+    fn parse_delete_expression(&mut self) -> Result<ast::Expression, Error> {
+        let start = self.current_span;
+        // delete(m, key) は delete m[key] と同じ
+        if self.peek_token_is(Token::LPAREN) {
+            self.next_token();
+            return self.parse_delete_call(start);
+        }
+        self.next_token();
+        let target_start = self.current_span;
+        let target = self.parse_expression(Precedence::Prefix)?;
+        if !matches!(target, ast::Expression::Index { .. }) {
+            return Err(ParseError {
+                msg: "`delete` expects an index expression like m[key]".to_string(),
+                span: Some(target_start),
+            });
+        }
+        Ok(ast::Expression::Delete {
+            target: Box::new(target),
+            span: start.to(self.current_span),
+        })
+    }
+
+    fn parse_delete_call(&mut self, start: Span) -> Result<ast::Expression, Error> {
+        let mut arguments = self.parse_expressions(Token::RPAREN)?;
+        let span = start.to(self.current_span);
+        if arguments.len() != 2 {
+            return Err(ParseError {
+                msg: format!(
+                    "`delete` takes a target and a key but got {} arguments",
+                    arguments.len()
+                ),
+                span: Some(span),
+            });
+        }
+        let index = arguments.pop().unwrap();
+        let left = arguments.pop().unwrap();
+        Ok(ast::Expression::Delete {
+            target: Box::new(ast::Expression::Index {
+                left: Box::new(left),
+                index: Box::new(index),
+                span,
+            }),
+            span,
+        })
+    }
+
+    fn parse_while_expression(&mut self) -> Result<ast::Expression, Error> {
+        let start = self.current_span;
+        if !self.expect_peek(Token::LPAREN) {
+            return Err(ParseError {
+                msg: format!("Unexpected token {}. wanted LPAREN", self.peek_token),
+                span: Some(self.peek_span),
+            });
+        }
+        self.next_token();
+        let condition = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(Token::RPAREN) {
+            return Err(ParseError {
+                msg: "Parentheses are not closed. in while expression".to_string(),
+                span: Some(self.peek_span),
+            });
+        }
+        if !self.expect_peek(Token::LBRACE) {
+            return Err(ParseError {
+                msg: format!("Unexpected token {}. wanted `{{`", self.peek_token),
+                span: Some(self.peek_span),
+            });
+        }
+        let statement = self.parse_block_statement()?;
+        Ok(ast::Expression::While {
+            condition: Box::new(condition),
+            statement: Box::new(statement),
+            span: start.to(self.current_span),
         })
     }
 
@@ -234,10 +404,14 @@ impl<'a> Parser<'a> {
         self.next_token();
         let mut identifiers = vec![];
         let identifier = match self.current_token.clone() {
-            Token::IDENT(ident) => ast::Expression::Identifier(ident),
+            Token::IDENT(ident) => ast::Expression::Identifier {
+                name: ident,
+                span: self.current_span,
+            },
             t => {
                 return Err(ParseError {
-                    msg: format!("Expected identifier, but got {:?}", t),
+                    msg: format!("Expected identifier, but got {}", t),
+                    span: Some(self.current_span),
                 })
             }
         };
@@ -249,10 +423,14 @@ impl<'a> Parser<'a> {
             self.next_token();
 
             let identifier = match self.current_token.clone() {
-                Token::IDENT(ident) => ast::Expression::Identifier(ident),
+                Token::IDENT(ident) => ast::Expression::Identifier {
+                    name: ident,
+                    span: self.current_span,
+                },
                 t => {
                     return Err(ParseError {
-                        msg: format!("Expected identifier, but got {:?}", t),
+                        msg: format!("Expected identifier, but got {}", t),
+                        span: Some(self.current_span),
                     })
                 }
             };
@@ -261,28 +439,33 @@ impl<'a> Parser<'a> {
 
         if !self.expect_peek(Token::RPAREN) {
             return Err(ParseError {
-                msg: format!("Unexpected token {:?}. wanted `}}`", self.peek_token),
+                msg: format!("Unexpected token {}. wanted `}}`", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
         Ok(identifiers)
     }
 
     fn parse_function_expression(&mut self) -> Result<ast::Expression, Error> {
+        let start = self.current_span;
         if !self.expect_peek(Token::LPAREN) {
             return Err(ParseError {
-                msg: format!("Unexpected token {:?}. wanted `(`", self.peek_token),
+                msg: format!("Unexpected token {}. wanted `(`", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
         let parameters = self.parse_function_params()?;
         if !self.expect_peek(Token::LBRACE) {
             return Err(ParseError {
-                msg: format!("Unexpected token {:?}. wanted `{{`", self.peek_token),
+                msg: format!("Unexpected token {}. wanted `{{`", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
         let body = self.parse_block_statement()?;
         Ok(ast::Expression::Function {
             parameters,
             body: Box::new(body),
+            span: start.to(self.current_span),
         })
     }
 
@@ -303,32 +486,40 @@ impl<'a> Parser<'a> {
         if !self.expect_peek(token.clone()) {
             return Err(ParseError {
                 msg: format!("Expect {} but got {}", token, self.peek_token),
+                span: Some(self.peek_span),
             });
         }
         Ok(expressions)
     }
 
     fn parse_array_expression(&mut self) -> Result<ast::Expression, Error> {
+        let start = self.current_span;
         let expressions = self.parse_expressions(Token::RBRACKET)?;
-        Ok(ast::Expression::Array(expressions))
+        Ok(ast::Expression::Array {
+            elements: expressions,
+            span: start.to(self.current_span),
+        })
     }
 
     fn parse_map_expression(&mut self) -> Result<ast::Expression, Error> {
-        let mut m = BTreeMap::new();
+        let start = self.current_span;
+        let mut m = vec![];
         while !self.peek_token_is(Token::RBRACE) {
             self.next_token();
             let key = self.parse_expression(Precedence::Lowest)?;
             if !self.expect_peek(Token::COLON) {
                 return Err(ParseError {
                     msg: format!("Expect `:` but got {}", self.peek_token),
+                    span: Some(self.peek_span),
                 });
             }
             self.next_token();
             let value = self.parse_expression(Precedence::Lowest)?;
-            m.insert(Box::new(key), Box::new(value));
+            m.push((Box::new(key), Box::new(value)));
             if !self.peek_token_is(Token::RBRACE) && !self.expect_peek(Token::COMMA) {
                 return Err(ParseError {
                     msg: format!("Expect `}}` or `:` but got {}", self.peek_token),
+                    span: Some(self.peek_span),
                 });
             }
         }
@@ -336,95 +527,188 @@ impl<'a> Parser<'a> {
         if !self.expect_peek(Token::RBRACE) {
             return Err(ParseError {
                 msg: format!("Expect `}}` but got {}", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
-        Ok(ast::Expression::Map(m))
+        Ok(ast::Expression::Map {
+            pairs: m,
+            span: start.to(self.current_span),
+        })
+    }
+
+    // `${}`の中身はそれぞれ別のparserで1つの式としてparseする
+    fn parse_interpolated(&mut self, parts: Vec<StringPart>) -> Result<ast::Expression, Error> {
+        let span = self.current_span;
+        let mut expressions = vec![];
+        for part in parts {
+            match part {
+                // 文字列の部分は位置を持たないので、文字列全体の位置を使う
+                StringPart::Literal(s) => {
+                    expressions.push(ast::Expression::String { value: s, span })
+                }
+                StringPart::Code(code, position) => {
+                    let mut lexer = Lexer::new_at(&code, position);
+                    let mut parser = Parser::new(&mut lexer);
+                    if parser.current_token_is(Token::EOF) {
+                        return Err(ParseError {
+                            msg: "empty interpolation".to_string(),
+                            span: Some(self.current_span),
+                        });
+                    }
+                    let expression = parser.parse_expression(Precedence::Lowest);
+                    // ${}の中のblockで回復したエラーもこちらのエラーとして報告する
+                    self.errors.append(&mut parser.errors);
+                    let expression = expression?;
+                    if !parser.peek_token_is(Token::EOF) {
+                        return Err(ParseError {
+                            msg: format!("unexpected `{}` in interpolation", parser.peek_token),
+                            span: Some(parser.peek_span),
+                        });
+                    }
+                    expressions.push(expression);
+                }
+            }
+        }
+        Ok(ast::Expression::Interpolated {
+            parts: expressions,
+            span,
+        })
+    }
+
+    fn parse_for_parameter(&mut self) -> Result<String, Error> {
+        match &self.current_token {
+            Token::IDENT(name) => Ok(name.clone()),
+            t => Err(ParseError {
+                msg: format!("Expect identifier but got {}", t),
+                span: Some(self.current_span),
+            }),
+        }
     }
 
     fn parse_for_expression(&mut self) -> Result<ast::Expression, Error> {
+        let start = self.current_span;
         // forの次に進む
         self.next_token();
         // identifierをparse
-        let parameter = match self.parse_expression(Precedence::Lowest)? {
-            ast::Expression::Identifier(i) => i,
-            o => {
-                return Err(Error::EvalError {
-                    msg: format!("Expect identifier but got {}", o),
-                })
-            }
+        let parameter = self.parse_for_parameter()?;
+        // `,`があれば2つ目のidentifierをparse
+        let value = if self.expect_peek(Token::COMMA) {
+            self.next_token();
+            Some(self.parse_for_parameter()?)
+        } else {
+            None
         };
         // inを読み込み(なければエラー)
         if !self.expect_peek(Token::IN) {
-            return Err(Error::EvalError {
+            return Err(ParseError {
                 msg: format!("Expect `in` but got {}", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
-        if !self.expect_peek(Token::LBRACKET) {
-            return Err(Error::EvalError {
-                msg: format!("Expect `[` but got {}", self.peek_token),
-            });
-        }
-        // arrayのparse
-        let array = self.parse_array_expression()?;
+        self.next_token();
+        // 繰り返す対象のparse
+        let iterable = self.parse_expression(Precedence::Lowest)?;
         // {}内のparse
         if !self.expect_peek(Token::LBRACE) {
-            return Err(Error::EvalError {
+            return Err(ParseError {
                 msg: format!("Expect `{{` but got {}", self.peek_token),
+                span: Some(self.peek_span),
             });
         }
         let statement = self.parse_block_statement()?;
         Ok(ast::Expression::For {
             parameter,
-            array: Box::new(array),
+            value,
+            iterable: Box::new(iterable),
             statement: Box::new(statement),
+            span: start.to(self.current_span),
         })
     }
 
     fn parse_prefix(&mut self) -> Result<ast::Expression, Error> {
         match self.current_token.clone() {
             Token::IDENT(ident) => {
-                if self.peek_token_is(Token::ASSIGN) {
+                let identifier = ast::Expression::Identifier {
+                    name: ident,
+                    span: self.current_span,
+                };
+                if self.peek_token_is_assign() {
+                    let start = self.current_span;
                     self.next_token();
-                    self.parse_infix(ast::Expression::Identifier(ident))
+                    self.parse_infix(identifier, start)
                 } else {
-                    Ok(ast::Expression::Identifier(ident))
+                    Ok(identifier)
                 }
             }
-            Token::INT(i) => Ok(ast::Expression::Integer(i)),
-            Token::STRING(s) => Ok(ast::Expression::String(s)),
-            Token::TRUE => Ok(ast::Expression::Bool(true)),
-            Token::FALSE => Ok(ast::Expression::Bool(false)),
+            Token::INT(i) => Ok(ast::Expression::Integer {
+                value: i,
+                span: self.current_span,
+            }),
+            Token::FLOAT(f) => Ok(ast::Expression::Float {
+                value: ast::FloatLiteral(f),
+                span: self.current_span,
+            }),
+            Token::STRING(s) => Ok(ast::Expression::String {
+                value: s,
+                span: self.current_span,
+            }),
+            Token::INTERPOLATED(parts) => self.parse_interpolated(parts),
+            Token::TRUE => Ok(ast::Expression::Bool {
+                value: true,
+                span: self.current_span,
+            }),
+            Token::FALSE => Ok(ast::Expression::Bool {
+                value: false,
+                span: self.current_span,
+            }),
+            Token::NULL => Ok(ast::Expression::Null {
+                span: self.current_span,
+            }),
             Token::IF => self.parse_if_expression(),
             Token::FUNCTION => self.parse_function_expression(),
             Token::MINUS => {
+                let start = self.current_span;
                 self.next_token();
+                let right = self.parse_expression(Precedence::Prefix)?;
                 Ok(ast::Expression::Prefix {
                     operator: ast::PrefixOprator::Minus,
-                    right: Box::new(self.parse_expression(Precedence::Prefix)?),
+                    right: Box::new(right),
+                    span: start.to(self.current_span),
                 })
             }
             Token::LPAREN => self.parse_group_expression(),
             Token::LBRACKET => self.parse_array_expression(),
             Token::LBRACE => self.parse_map_expression(),
             Token::BANG => {
+                let start = self.current_span;
                 self.next_token();
+                let right = self.parse_expression(Precedence::Prefix)?;
                 Ok(ast::Expression::Prefix {
                     operator: ast::PrefixOprator::Bang,
-                    right: Box::new(self.parse_expression(Precedence::Prefix)?),
+                    right: Box::new(right),
+                    span: start.to(self.current_span),
                 })
             }
             Token::FOR => self.parse_for_expression(),
+            Token::WHILE => self.parse_while_expression(),
+            Token::DELETE => self.parse_delete_expression(),
+            Token::ILLEGAL(msg) => Err(ParseError {
+                msg,
+                span: Some(self.current_span),
+            }),
             t => Err(ParseError {
                 msg: format!("Unexpected Prefix Expression: {}", t),
+                span: Some(self.current_span),
             }),
         }
     }
 
     fn parse_expression(&mut self, precedence: Precedence) -> Result<ast::Expression, Error> {
+        let start = self.current_span;
         let mut expression = self.parse_prefix()?;
         while !self.peek_token_is(Token::SEMICOLON) && precedence < self.peek_precedence() {
             self.next_token();
-            expression = self.parse_infix(expression)?;
+            expression = self.parse_infix(expression, start)?;
         }
         Ok(expression)
     }
@@ -437,22 +721,81 @@ impl<'a> Parser<'a> {
         Ok(ast::Statement::Expression(expression))
     }
 
+    // break, continue
+    fn parse_loop_control_statement(&mut self) -> ast::Statement {
+        let statement = match self.current_token {
+            Token::BREAK => ast::Statement::Break(self.current_span),
+            _ => ast::Statement::Continue(self.current_span),
+        };
+        if self.peek_token_is(Token::SEMICOLON) {
+            self.next_token();
+        }
+        statement
+    }
+
     fn parse_statement(&mut self) -> Result<ast::Statement, Error> {
         match &self.current_token {
             Token::LET => Ok(self.parse_let_statement()?),
             Token::RETURN => Ok(self.parse_return_statement()?),
+            Token::BREAK | Token::CONTINUE => Ok(self.parse_loop_control_statement()),
             _ => Ok(self.parse_expression_statement()?),
         }
     }
 
-    pub fn parse_program(&mut self) -> Result<ast::Program, Error> {
+    // エラーの後は文の区切りまで読み飛ばし、次の文からparseを再開する
+    // `{}`の中身は丸ごと飛ばすので、ブロックの途中にある`;`や`}`では止まらない
+    // 対応する`{`のない`}`では止まって読み進めず、trueを返す。そこで外側のblockが閉じるため
+    fn synchronize(&mut self) -> bool {
+        let mut depth = 0;
+        loop {
+            match self.current_token {
+                Token::EOF => return false,
+                Token::LBRACE => depth += 1,
+                Token::RBRACE if depth > 0 => depth -= 1,
+                Token::RBRACE => return true,
+                Token::SEMICOLON if depth == 0 => return false,
+                _ => {}
+            }
+            if depth == 0
+                && matches!(
+                    self.peek_token,
+                    Token::LET
+                        | Token::RETURN
+                        | Token::BREAK
+                        | Token::CONTINUE
+                        | Token::RBRACE
+                        | Token::EOF
+                )
+            {
+                return false;
+            }
+            self.next_token();
+        }
+    }
+
+    // エラーがあっても最後までparseし、parseできた部分のProgramと全てのエラーを返す
+    pub fn parse(&mut self) -> (ast::Program, Vec<Error>) {
         let mut program = ast::Program { statements: vec![] };
         while !self.current_token_is(Token::EOF) {
-            let statement = self.parse_statement()?;
-            program.statements.push(statement);
+            match self.parse_statement() {
+                Ok(stmt) => program.statements.push(stmt),
+                Err(e) => {
+                    self.errors.push(e);
+                    self.synchronize();
+                }
+            }
             self.next_token();
         }
-        Ok(program)
+        (program, std::mem::take(&mut self.errors))
+    }
+
+    pub fn parse_program(&mut self) -> Result<ast::Program, Vec<Error>> {
+        let (program, errors) = self.parse();
+        if errors.is_empty() {
+            Ok(program)
+        } else {
+            Err(errors)
+        }
     }
 }
 
@@ -470,12 +813,38 @@ mod test {
         let mut parser = Parser::new(&mut lexer);
         let program = parser.parse_program().unwrap();
         assert_eq!(program.statements.len(), 3);
-        let tests = vec!["let x = 5;", "let y = 10;", "let foobar = 838383;"];
+        let tests = ["let x = 5;", "let y = 10;", "let foobar = 838383;"];
         for (index, stmt) in program.statements.iter().enumerate() {
             assert_eq!(format!("{}", stmt), tests[index]);
         }
     }
 
+    #[test]
+    fn test_let_doc_comment() {
+        let input = r#"
+            /// 足し算をする
+            /// 引数は2つ
+            let add = fn(a, b) { a + b };
+            // ただのコメント
+            let x = 1;
+            /// 式に付いたdocは捨てられる
+            x;
+            let y = 2;"#;
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(&mut lexer);
+        let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+        assert_eq!(program.statements.len(), 4);
+        let docs: Vec<Option<&str>> = program
+            .statements
+            .iter()
+            .filter_map(|s| match s {
+                ast::Statement::Let { doc, .. } => Some(doc.as_deref()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(docs, vec![Some("足し算をする\n引数は2つ"), None, None]);
+    }
+
     #[test]
     fn test_return_statement() {
         let input = r#"
@@ -487,7 +856,7 @@ mod test {
         let mut parser = Parser::new(&mut lexer);
         let program = parser.parse_program().unwrap();
         assert_eq!(program.statements.len(), 3);
-        let tests = vec!["return 5;", "return 10;", "return 993322;"];
+        let tests = ["return 5;", "return 10;", "return 993322;"];
         for (index, stmt) in program.statements.iter().enumerate() {
             assert_eq!(format!("{}", stmt), tests[index]);
         }
@@ -502,10 +871,10 @@ mod test {
         assert_eq!(program.statements.len(), 1);
         let ident = match &program.statements[0] {
             ast::Statement::Expression(e) => match e {
-                ast::Expression::Identifier(s) => s,
+                ast::Expression::Identifier { name, .. } => name,
                 _ => "unreach",
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
         assert_eq!(ident, "foobar");
     }
@@ -519,10 +888,10 @@ mod test {
         assert_eq!(program.statements.len(), 1);
         let ident = match &program.statements[0] {
             ast::Statement::Expression(e) => match e {
-                ast::Expression::Integer(i) => i,
+                ast::Expression::Integer { value: i, .. } => i,
                 _ => &999,
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
         assert_eq!(ident, &5);
     }
@@ -542,10 +911,10 @@ mod test {
         for (index, stmt) in program.statements.iter().enumerate() {
             let exp = match stmt {
                 ast::Statement::Expression(e) => match e {
-                    ast::Expression::Bool(b) => b,
-                    _ => panic!(format!("expect `Bool` but got {:?}", e),),
+                    ast::Expression::Bool { value: b, .. } => b,
+                    _ => panic!("expect `Bool` but got {:?}", e),
                 },
-                e => panic!(format!("expect `Expression` but got {:?}", e),),
+                e => panic!("expect `Expression` but got {:?}", e),
             };
             assert_eq!(exp, &tests[index]);
         }
@@ -569,16 +938,18 @@ mod test {
         for (index, stmt) in program.statements.iter().enumerate() {
             let prefix = match stmt {
                 ast::Statement::Expression(e) => match e {
-                    ast::Expression::Prefix { operator, right } => (
+                    ast::Expression::Prefix {
+                        operator, right, ..
+                    } => (
                         operator,
                         match right.as_ref() {
-                            ast::Expression::Integer(i) => i,
+                            ast::Expression::Integer { value: i, .. } => i,
                             _ => panic!("Invalid Right hand"),
                         },
                     ),
                     _ => panic!("Invalid Prefix Expression"),
                 },
-                e => panic!(format!("expect `Expression` but got {:?}", e),),
+                e => panic!("expect `Expression` but got {:?}", e),
             };
             assert_eq!(*prefix.0, tests[index].0);
             assert_eq!(*prefix.1, tests[index].1);
@@ -596,11 +967,15 @@ mod test {
             5 > 5;
             5 == 5;
             5 != 5;
+            5 % 5;
+            5 ** 5;
+            5 <= 5;
+            5 >= 5;
         "#;
         let mut lexer = Lexer::new(input);
         let mut parser = Parser::new(&mut lexer);
         let program = parser.parse_program().unwrap();
-        assert_eq!(program.statements.len(), 8);
+        assert_eq!(program.statements.len(), 12);
 
         let tests = [
             (5, ast::InfixOprator::Plus, 5),
@@ -611,6 +986,10 @@ mod test {
             (5, ast::InfixOprator::Gt, 5),
             (5, ast::InfixOprator::Equal, 5),
             (5, ast::InfixOprator::Nequal, 5),
+            (5, ast::InfixOprator::Percent, 5),
+            (5, ast::InfixOprator::Pow, 5),
+            (5, ast::InfixOprator::LtEq, 5),
+            (5, ast::InfixOprator::GtEq, 5),
         ];
 
         for (index, stmt) in program.statements.iter().enumerate() {
@@ -620,20 +999,21 @@ mod test {
                         left,
                         operator,
                         right,
+                        ..
                     } => (
                         match left.as_ref() {
-                            ast::Expression::Integer(i) => i,
+                            ast::Expression::Integer { value: i, .. } => i,
                             _ => panic!("Invalid left hand"),
                         },
                         operator,
                         match right.as_ref() {
-                            ast::Expression::Integer(i) => i,
+                            ast::Expression::Integer { value: i, .. } => i,
                             _ => panic!("Invalid right hand"),
                         },
                     ),
-                    e => panic!(format!("Invalid Infix Expression {:?}", e)),
+                    e => panic!("Invalid Infix Expression {:?}", e),
                 },
-                e => panic!(format!("expect `Expression` but got {:?}", e),),
+                e => panic!("expect `Expression` but got {:?}", e),
             };
             assert_eq!(*infix.0, tests[index].0);
             assert_eq!(*infix.1, tests[index].1);
@@ -664,20 +1044,21 @@ mod test {
                         left,
                         operator,
                         right,
+                        ..
                     } => (
                         match left.as_ref() {
-                            ast::Expression::Bool(b) => b,
+                            ast::Expression::Bool { value: b, .. } => b,
                             _ => panic!("Invalid left hand"),
                         },
                         operator,
                         match right.as_ref() {
-                            ast::Expression::Bool(b) => b,
+                            ast::Expression::Bool { value: b, .. } => b,
                             _ => panic!("Invalid right hand"),
                         },
                     ),
-                    e => panic!(format!("Invalid Infix Expression {:?}", e)),
+                    e => panic!("Invalid Infix Expression {:?}", e),
                 },
-                e => panic!(format!("expect `Expression` but got {:?}", e),),
+                e => panic!("expect `Expression` but got {:?}", e),
             };
             assert_eq!(*infix.0, tests[index].0);
             assert_eq!(*infix.1, tests[index].1);
@@ -703,6 +1084,7 @@ mod test {
                     condition,
                     consequence,
                     alternative,
+                    ..
                 } => {
                     assert_eq!(format!("{}", condition.as_ref()), "(x < y)");
                     assert_eq!(format!("{}", consequence.as_ref()), "x");
@@ -710,9 +1092,9 @@ mod test {
                         assert_eq!(format!("{}", a.as_ref()), "y");
                     };
                 }
-                e => panic!(format!("Invalid Infix Expression {:?}", e)),
+                e => panic!("Invalid Infix Expression {:?}", e),
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
     }
 
@@ -734,6 +1116,7 @@ mod test {
                     condition,
                     consequence,
                     alternative,
+                    ..
                 } => {
                     assert_eq!(format!("{}", condition.as_ref()), "(x < y)");
                     assert_eq!(format!("{}", consequence.as_ref()), "x");
@@ -741,9 +1124,9 @@ mod test {
                         assert_eq!(format!("{}", a.as_ref()), "y");
                     };
                 }
-                e => panic!(format!("Invalid Infix Expression {:?}", e)),
+                e => panic!("Invalid Infix Expression {:?}", e),
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
     }
 
@@ -761,14 +1144,16 @@ mod test {
         // panicしなければOK
         match stmt {
             ast::Statement::Expression(e) => match e {
-                ast::Expression::Function { parameters, body } => {
+                ast::Expression::Function {
+                    parameters, body, ..
+                } => {
                     assert_eq!(format!("{}", parameters[0]), "x");
                     assert_eq!(format!("{}", parameters[1]), "y");
                     assert_eq!(format!("{}", body), "(x + y)");
                 }
-                e => panic!(format!("Invalid Function Expression {:?}", e)),
+                e => panic!("Invalid Function Expression {:?}", e),
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
     }
 
@@ -789,6 +1174,7 @@ mod test {
                 ast::Expression::Call {
                     function,
                     arguments,
+                    ..
                 } => {
                     assert_eq!(format!("{}", arguments[0]), "1");
                     assert_eq!(format!("{}", arguments[1]), "(2 * 3)");
@@ -796,9 +1182,9 @@ mod test {
                     assert_eq!(format!("{}", function), "add");
                     assert_eq!(format!("{}", e), "add(1, (2 * 3), (4 + 5))");
                 }
-                e => panic!(format!("Invalid Function Expression {:?}", e)),
+                e => panic!("Invalid Function Expression {:?}", e),
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
     }
 
@@ -812,12 +1198,12 @@ mod test {
         let stmt = &program.statements[0];
         match stmt {
             ast::Statement::Expression(e) => match e {
-                ast::Expression::String(s) => {
+                ast::Expression::String { value: s, .. } => {
                     assert_eq!(format!("{}", s), "hello world");
                 }
-                e => panic!(format!("Invalid String Expression {:?}", e)),
+                e => panic!("Invalid String Expression {:?}", e),
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
     }
 
@@ -831,18 +1217,20 @@ mod test {
         let stmt = &program.statements[0];
         match stmt {
             ast::Statement::Expression(e) => match e {
-                ast::Expression::Array(array) => {
+                ast::Expression::Array {
+                    elements: array, ..
+                } => {
                     for (expr, expect) in array.iter().zip(vec![1, 2]) {
-                        if let ast::Expression::Integer(i) = expr {
+                        if let ast::Expression::Integer { value: i, .. } = expr {
                             assert_eq!(*i, expect);
                         } else {
                             panic!("unexpected expression {:?}", expr)
                         }
                     }
                 }
-                e => panic!(format!("Invalid String Expression {:?}", e)),
+                e => panic!("Invalid String Expression {:?}", e),
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
     }
 
@@ -856,52 +1244,140 @@ mod test {
         match stmt {
             ast::Statement::Expression(e) => match e {
                 ast::Expression::Index { .. } => assert_eq!(format!("{}", e), "hoge[(1 + 2)]"),
-                e => panic!(format!("Invalid String Expression {:?}", e)),
+                e => panic!("Invalid String Expression {:?}", e),
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
     }
 
     #[test]
     fn test_parse_map() {
-        let input = r#"{"one":1, "two": 2}"#;
+        let input = r#"{"two": 2, "one":1}"#;
         let mut lexer = Lexer::new(input);
         let mut parser = Parser::new(&mut lexer);
         let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
         let stmt = &program.statements[0];
         match stmt {
             ast::Statement::Expression(e) => match e {
-                ast::Expression::Map(_) => assert_eq!(format!("{}", e), "{ one: 1, two: 2 }"),
-                e => panic!(format!("Invalid String Expression {:?}", e)),
+                // 書かれた順のまま
+                ast::Expression::Map { .. } => assert_eq!(format!("{}", e), "{ two: 2, one: 1 }"),
+                e => panic!("Invalid String Expression {:?}", e),
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
     }
 
     #[test]
     fn test_parse_for() {
-        let input = r#" for a in [1,2,3] { a; }"#;
+        let tests = vec![
+            (" for a in [1,2,3] { a; }", "a", None, "[1, 2, 3]", "a"),
+            ("for a in xs { a }", "a", None, "xs", "a"),
+            ("for k, v in f(m) { v }", "k", Some("v"), "f(m)", "v"),
+        ];
+        for (input, expect_parameter, expect_value, expect_iterable, expect_statement) in tests {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+            let stmt = &program.statements[0];
+            match stmt {
+                ast::Statement::Expression(e) => match e {
+                    ast::Expression::For {
+                        parameter,
+                        value,
+                        iterable,
+                        statement,
+                        ..
+                    } => {
+                        assert_eq!(format!("{}", parameter), expect_parameter);
+                        assert_eq!(value.as_deref(), expect_value);
+                        assert_eq!(format!("{}", iterable), expect_iterable);
+                        assert_eq!(format!("{}", statement), expect_statement);
+                    }
+                    e => panic!("Invalid For Expression {:?}", e),
+                },
+                e => panic!("expect `Expression` but got {:?}", e),
+            };
+        }
+    }
+
+    #[test]
+    fn test_parse_while() {
+        let input = r#"while (a < 10) { if (a == 5) { break; } continue }"#;
         let mut lexer = Lexer::new(input);
         let mut parser = Parser::new(&mut lexer);
         let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
         let stmt = &program.statements[0];
         match stmt {
             ast::Statement::Expression(e) => match e {
-                ast::Expression::For {
-                    parameter,
-                    array,
+                ast::Expression::While {
+                    condition,
                     statement,
+                    ..
                 } => {
-                    assert_eq!(format!("{}", parameter), "a");
-                    assert_eq!(format!("{}", array), "[1, 2, 3]");
-                    assert_eq!(format!("{}", statement), "a");
+                    assert_eq!(format!("{}", condition), "(a < 10)");
+                    match statement.as_ref() {
+                        ast::Statement::Block(stmts) => {
+                            assert_eq!(stmts.len(), 2);
+                            assert!(matches!(stmts[1], ast::Statement::Continue(_)));
+                        }
+                        s => panic!("expect `Block` but got {:?}", s),
+                    }
                 }
-                e => panic!(format!("Invalid For Expression {:?}", e)),
+                e => panic!("Invalid While Expression {:?}", e),
             },
-            e => panic!(format!("expect `Expression` but got {:?}", e),),
+            e => panic!("expect `Expression` but got {:?}", e),
         };
     }
 
+    #[test]
+    fn test_parse_index_assign() {
+        let tests = vec![
+            ("a[0] = 1", "(a[0] = 1)"),
+            (r#"a["x"][0] = 1 + 2"#, "(a[x][0] = (1 + 2))"),
+            ("a[i + 1] += 2", "(a[(i + 1)] += 2)"),
+            ("a[0] == 1", "(a[0] == 1)"),
+        ];
+        for (input, expect) in tests {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+            assert_eq!(format!("{}", program.statements[0]), expect);
+        }
+    }
+
+    #[test]
+    fn test_parse_delete() {
+        let tests = vec![
+            (r#"delete m["a"]"#, "delete m[a]"),
+            ("delete a[0][i + 1]", "delete a[0][(i + 1)]"),
+            (r#"delete(m, "a")"#, "delete m[a]"),
+            ("delete(m[0], i + 1)", "delete m[0][(i + 1)]"),
+        ];
+        for (input, expect) in tests {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+            assert_eq!(format!("{}", program.statements[0]), expect);
+        }
+
+        // 対象の式は表示しない
+        let mut lexer = Lexer::new("delete fn() { 1 }");
+        let mut parser = Parser::new(&mut lexer);
+        let errors = parser.parse_program().unwrap_err();
+        assert_eq!(
+            format!("{}", errors[0]),
+            "ParseError: `delete` expects an index expression like m[key]"
+        );
+
+        let mut lexer = Lexer::new("delete(m)");
+        let mut parser = Parser::new(&mut lexer);
+        let errors = parser.parse_program().unwrap_err();
+        assert_eq!(
+            format!("{}", errors[0]),
+            "ParseError: `delete` takes a target and a key but got 1 arguments"
+        );
+    }
+
     #[test]
     fn test_parse_assign() {
         let input = r#"let a = 10; a = 11;"#;
@@ -914,12 +1390,200 @@ mod test {
                 ast::Expression::Infix { .. } => {
                     assert_eq!(format!("{}", e), "(a = 11)");
                 }
-                e => panic!(format!("Invalid Assign Expression {:?}", e)),
+                e => panic!("Invalid Assign Expression {:?}", e),
             },
-            e => panic!(format!(
-                "expect `Expression` but got {:?}, statement => {}",
-                e, stmt
-            ),),
+            e => panic!("expect `Expression` but got {:?}, statement => {}", e, stmt),
+        };
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let tests = vec![
+            ("a && b || c", "((a && b) || c)"),
+            ("a || b && c", "(a || (b && c))"),
+            ("a < b && b < c", "((a < b) && (b < c))"),
+            ("a == b || !c", "((a == b) || !c)"),
+            ("a || b || c", "((a || b) || c)"),
+            ("a <= b == b >= c", "((a <= b) == (b >= c))"),
+            ("a + b % c", "(a + (b % c))"),
+            ("a * b ** c", "(a * (b ** c))"),
+            ("a ** b ** c", "(a ** (b ** c))"),
+            ("-a ** b", "-(a ** b)"),
+            ("a += b * c", "(a += (b * c))"),
+            ("0..n + 1", "(0 .. (n + 1))"),
+            ("a == null || !b", "((a == null) || !b)"),
+            ("a..=b == c", "((a ..= b) == c)"),
+        ];
+        for (input, expect) in tests {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+            assert_eq!(format!("{}", program.statements[0]), expect);
+        }
+    }
+
+    #[test]
+    fn test_parse_interpolated() {
+        let input = r#""a ${b + 1} c""#;
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(&mut lexer);
+        let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+        match &program.statements[0] {
+            ast::Statement::Expression(ast::Expression::Interpolated { parts, .. }) => {
+                assert_eq!(parts.len(), 3);
+                assert_eq!(format!("{}", parts[1]), "(b + 1)");
+            }
+            s => panic!("expect `Interpolated` but got {:?}", s),
+        }
+
+        let tests = vec![
+            (r#""${}""#, "ParseError: empty interpolation", (1, 1)),
+            (
+                r#""${a b}""#,
+                "ParseError: unexpected `b` in interpolation",
+                (1, 6),
+            ),
+            (
+                r#""x ${let}""#,
+                "ParseError: Unexpected Prefix Expression: LET",
+                (1, 6),
+            ),
+        ];
+        for (input, expect, position) in tests {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            let e = parser.parse_program().unwrap_err();
+            assert_eq!(format!("{}", e[0]), expect);
+            let span = e[0].span().unwrap();
+            assert_eq!((span.start.line, span.start.column), position);
+        }
+    }
+
+    #[test]
+    fn test_parse_span() {
+        let input = "let a = 1;\nfoo(a + b)";
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(&mut lexer);
+        let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+        match &program.statements[1] {
+            ast::Statement::Expression(ast::Expression::Call {
+                arguments, span, ..
+            }) => {
+                assert_eq!((span.start.line, span.start.column), (2, 1));
+                assert_eq!((span.end.line, span.end.column), (2, 11));
+                let arg = arguments[0].span();
+                assert_eq!((arg.start.column, arg.end.column), (5, 10));
+            }
+            s => panic!("expect `Call` but got {:?}", s),
         };
+
+        // リテラルや配列、連想配列、関数も位置を持つ
+        let tests = vec![
+            ("1.5", (1, 4)),
+            ("null", (1, 5)),
+            (r#""abc""#, (1, 6)),
+            ("[1, 2]", (1, 7)),
+            ("{1: 2}", (1, 7)),
+            ("fn(x) { x }", (1, 12)),
+        ];
+        for (input, (start, end)) in tests {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+            match &program.statements[0] {
+                ast::Statement::Expression(e) => {
+                    let span = e.span();
+                    assert_eq!((span.start.column, span.end.column), (start, end));
+                }
+                s => panic!("expect `Expression` but got {:?}", s),
+            }
+        }
+
+        let input = "let a = [1];
+return a;";
+        let mut lexer = Lexer::new(input);
+        let mut parser = Parser::new(&mut lexer);
+        let program = parser.parse_program().unwrap_or_else(|e| panic!("{:?}", e));
+        match &program.statements[..] {
+            [ast::Statement::Let { span: l, .. }, ast::Statement::Return { span: r, .. }] => {
+                assert_eq!((l.start.line, l.start.column, l.end.column), (1, 1, 13));
+                assert_eq!((r.start.line, r.start.column, r.end.column), (2, 1, 10));
+            }
+            s => panic!("expect `Let` and `Return` but got {:?}", s),
+        }
+    }
+
+    #[test]
+    fn test_parse_error_span() {
+        let tests = vec![
+            ("let a = 1;\nlet = 2", (2, 5)),
+            ("if (true) { 1 } else 2", (1, 22)),
+            ("[1, 2", (1, 6)),
+            ("let a = 99999999999999999999;", (1, 9)),
+            ("delete fn() { 1 }", (1, 8)),
+        ];
+        for (input, expect) in tests {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            match parser.parse_program() {
+                Ok(p) => panic!("expect error but got {}", p),
+                Err(e) => {
+                    let span = e[0].span().unwrap();
+                    assert_eq!((span.start.line, span.start.column), expect);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_error_recovery() {
+        // (入力, エラーの位置, parseできた文の数, 最後の文)
+        let tests = vec![
+            (
+                "let = 1; let b = 2; let c 3; let d = 4;",
+                vec![(1, 5), (1, 27)],
+                2,
+                "let d = 4;",
+            ),
+            (
+                "let f = fn() {\n  let = 1;\n  2\n};\nlet = 3;\nf()",
+                vec![(2, 7), (5, 5)],
+                2,
+                "f()",
+            ),
+            (
+                "if (true) { let a = ; }\nlet b = 1",
+                vec![(1, 21)],
+                2,
+                "let b = 1;",
+            ),
+            // blockを閉じる`}`の上でエラーになっても、その`}`でblockを閉じる
+            (
+                "let f = fn() {\n  1 +\n};\nlet b = 2;\nb",
+                vec![(3, 1)],
+                3,
+                "b",
+            ),
+            // ${}の中のblockのエラーも失われない
+            (
+                "let s = \"${fn() { let = 1; 2 }()}\";\nlet b = 1",
+                vec![(1, 23)],
+                2,
+                "let b = 1;",
+            ),
+        ];
+        for (input, positions, count, last) in tests {
+            let mut lexer = Lexer::new(input);
+            let mut parser = Parser::new(&mut lexer);
+            let (program, errors) = parser.parse();
+            let got: Vec<_> = errors
+                .iter()
+                .map(|e| e.span().unwrap())
+                .map(|s| (s.start.line, s.start.column))
+                .collect();
+            assert_eq!(got, positions, "input: {}", input);
+            assert_eq!(program.statements.len(), count);
+            assert_eq!(format!("{}", program.statements[count - 1]), last);
+        }
     }
 }
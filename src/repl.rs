@@ -1,30 +1,156 @@
-use crate::environment::Environment;
-use crate::evaluator::*;
-use crate::lexer::*;
-use crate::parser::*;
-use std::io::Stdin;
+use rust_interpreter::lexer::Lexer;
+use rust_interpreter::token::Token;
+use rust_interpreter::{Diagnostic, Interpreter};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::env;
+use std::path::PathBuf;
 
-pub fn start(input: Stdin) {
-    let mut env = Environment::new();
-    println!("> Hello! here is rust interpreter");
+const HISTORY_FILE: &str = ".rust_interpreter_history";
+
+// 括弧が閉じていない・文字列やコメントが終わっていない・中置演算子で終わっている入力は途中とみなす
+fn is_incomplete(input: &str) -> bool {
+    let mut l = Lexer::new(input);
+    let mut depth = 0;
+    let mut last = Token::EOF;
     loop {
-        let mut s = String::new();
-        input.read_line(&mut s).ok();
-        let mut l = Lexer::new(&s);
-        let mut parser = Parser::new(&mut l);
-        let program = parser.parse_program();
-        if s == "exit\n" {
-            println!("See you!");
+        let token = l.next_token();
+        match token {
+            Token::LPAREN | Token::LBRACE | Token::LBRACKET => depth += 1,
+            Token::RPAREN | Token::RBRACE | Token::RBRACKET => depth -= 1,
+            Token::EOF => break,
+            Token::ILLEGAL(_) if l.is_unterminated() => return true,
+            _ => {}
+        }
+        last = token;
+    }
+    if depth > 0 {
+        return true;
+    }
+    matches!(
+        last,
+        Token::PLUS
+            | Token::MINUS
+            | Token::ASTERISK
+            | Token::SLASH
+            | Token::PERCENT
+            | Token::POW
+            | Token::LT
+            | Token::GT
+            | Token::LTEQ
+            | Token::GTEQ
+            | Token::DOTDOT
+            | Token::DOTDOTEQ
+            | Token::EQ
+            | Token::NEQ
+            | Token::AND
+            | Token::OR
+            | Token::BANG
+            | Token::ASSIGN
+            | Token::PLUSEQ
+            | Token::MINUSEQ
+            | Token::ASTERISKEQ
+            | Token::SLASHEQ
+            | Token::COMMA
+            | Token::COLON
+    )
+}
+
+// 履歴はホームディレクトリに保存する。HOMEが無い環境ではセッション内だけ保持する
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+// 入力が完結するまで続きの行を読み込む
+fn read_input(editor: &mut DefaultEditor) -> rustyline::Result<String> {
+    let mut s = editor.readline(">> ")?;
+    while is_incomplete(&s) {
+        s.push('\n');
+        s.push_str(&editor.readline(".. ")?);
+    }
+    Ok(s)
+}
+
+pub fn start() {
+    let mut interpreter = Interpreter::new();
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("can not start REPL: {}", e);
             return;
         }
-        match program {
-            Ok(p) => match eval(p, &mut env) {
-                Ok(result) => println!("> {}", result),
-                Err(e) => println!("> {}", e),
-            },
+    };
+    let history = history_path();
+    if let Some(path) = &history {
+        // 初回起動時はファイルが無いので読み込みの失敗は無視する
+        editor.load_history(path).ok();
+    }
+    println!("> Hello! here is rust interpreter");
+    loop {
+        match read_input(&mut editor) {
+            Ok(s) => {
+                if s.trim().is_empty() {
+                    continue;
+                }
+                editor.add_history_entry(s.as_str()).ok();
+                if s.trim() == "exit" {
+                    break;
+                }
+                match interpreter.eval_str(&s) {
+                    Ok(result) => println!("> {}", result),
+                    Err(errors) => {
+                        for e in errors {
+                            println!("{}", Diagnostic::new(&e, "repl", &s));
+                        }
+                    }
+                }
+            }
+            // Ctrl-Cは入力途中の内容を捨てて次の入力に移る
+            Err(ReadlineError::Interrupted) => continue,
+            // Ctrl-Dで終了
+            Err(ReadlineError::Eof) => break,
             Err(e) => {
-                println!("> {}", e);
+                eprintln!("> {}", e);
+                break;
             }
         }
     }
+    if let Some(path) = &history {
+        if let Err(e) = editor.save_history(path) {
+            eprintln!("can not save history: {}", e);
+        }
+    }
+    println!("See you!");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_incomplete() {
+        let tests = vec![
+            ("let a = 1;", false),
+            ("let f = fn(a) {", true),
+            ("let f = fn(a) {\n a + 1\n}", false),
+            ("add(1,", true),
+            ("[1, 2", true),
+            ("{\"a\": 1", true),
+            ("\"hello", true),
+            ("\"hello\"", false),
+            ("1 +", true),
+            ("let a =", true),
+            ("if (a == 1) { 1 } else { 2 }", false),
+            ("}", false),
+            ("/* comment", true),
+            ("1 /* a */", false),
+            ("\"a\" // \"", false),
+            (r#""\""#, true),
+            (r#""\"""#, false),
+            (r#""\"" + "a""#, false),
+        ];
+        for (input, expect) in tests {
+            assert_eq!(is_incomplete(input), expect, "input: {}", input);
+        }
+    }
 }
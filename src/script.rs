@@ -0,0 +1,69 @@
+use rust_interpreter::{Diagnostic, Error, Interpreter, Object};
+use std::fs;
+
+// sourceをまとめてparse・評価する。残りのコマンドライン引数は`args`として参照できる
+pub fn eval_script(source: &str, args: Vec<String>) -> Result<Object, Vec<Error>> {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global(
+        "args",
+        Object::Array(args.into_iter().map(Object::String).collect()),
+    );
+    interpreter.eval_str(source)
+}
+
+// pathのスクリプトを実行し、プロセスの終了コードを返す
+pub fn run(path: &str, args: Vec<String>) -> i32 {
+    let source = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("can not read {}: {}", path, e);
+            return 1;
+        }
+    };
+    match eval_script(&source, args) {
+        Ok(_) => 0,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("{}", Diagnostic::new(&e, path, &source));
+            }
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_eval_script() {
+        let input = r#"
+            let add = fn(a, b) { a + b };
+            let x = add(1, 2);
+            x + len(args)
+        "#;
+        match eval_script(input, vec!["a".to_string(), "b".to_string()]) {
+            Ok(Object::Integer(i)) => assert_eq!(i, 5),
+            o => panic!("Error expect 5 but got {:?}", o),
+        }
+    }
+
+    #[test]
+    fn test_script_args() {
+        match eval_script("args[1]", vec!["a".to_string(), "b".to_string()]) {
+            Ok(Object::String(s)) => assert_eq!(s, "b"),
+            o => panic!("Error expect `b` but got {:?}", o),
+        }
+    }
+
+    #[test]
+    fn test_script_error() {
+        let tests = vec![("let = 1", "ParseError"), ("1 + true", "EvalError")];
+        for (input, expect) in tests {
+            match eval_script(input, vec![]) {
+                Ok(o) => panic!("expect error but got {:?}", o),
+                Err(e) => assert!(format!("{}", e[0]).starts_with(expect)),
+            }
+        }
+    }
+}
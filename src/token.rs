@@ -1,23 +1,47 @@
+use crate::span::Position;
 use std::fmt;
 
+// `${}`を含む文字列の断片
+#[derive(Clone, Debug, PartialEq)]
+pub enum StringPart {
+    Literal(String),
+    // `${}`の中身のソースと、ソース上でのその開始位置
+    Code(String, Position),
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
-    ILLEGAL,
+    // 中身は不正な理由。parserがそのままエラーメッセージに使う
+    ILLEGAL(String),
     EOF,
     // identifiers
     IDENT(String),
-    INT(i32),
-    FLOAT(f32),
+    INT(i64),
+    FLOAT(f64),
     STRING(String),
+    INTERPOLATED(Vec<StringPart>),
     // operators
     ASSIGN,
+    PLUSEQ,
+    MINUSEQ,
+    ASTERISKEQ,
+    SLASHEQ,
     PLUS,
     MINUS,
     ASTERISK,
     SLASH,
+    PERCENT,
+    POW,
     LT,
     GT,
+    LTEQ,
+    GTEQ,
     BANG,
+    DOTDOT,
+    DOTDOTEQ,
+    AND,
+    OR,
     //delimiters
     COMMA,
     COLON,
@@ -40,25 +64,43 @@ pub enum Token {
     NEQ,
     FOR,
     IN,
+    WHILE,
+    BREAK,
+    CONTINUE,
+    NULL,
+    DELETE,
 }
 
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let value = match self {
-            Token::ILLEGAL => "ILLEGAL".to_string(),
+            Token::ILLEGAL(_) => "ILLEGAL".to_string(),
             Token::EOF => "EOF".to_string(),
             Token::IDENT(s) => s.to_string(),
             Token::INT(i) => format!("{}", i),
             Token::FLOAT(f) => format!("{}", f),
-            Token::STRING(s) => format!("{}", s),
+            Token::STRING(s) => s.to_string(),
+            Token::INTERPOLATED(_) => "INTERPOLATED".to_string(),
             Token::ASSIGN => "ASSIGN".to_string(),
+            Token::PLUSEQ => "PLUSEQ".to_string(),
+            Token::MINUSEQ => "MINUSEQ".to_string(),
+            Token::ASTERISKEQ => "ASTERISKEQ".to_string(),
+            Token::SLASHEQ => "SLASHEQ".to_string(),
             Token::PLUS => "PLUS".to_string(),
             Token::MINUS => "MINUS".to_string(),
             Token::ASTERISK => "ASTERISK".to_string(),
             Token::SLASH => "SLASH".to_string(),
+            Token::PERCENT => "PERCENT".to_string(),
+            Token::POW => "POW".to_string(),
             Token::LT => "LT".to_string(),
             Token::GT => "GT".to_string(),
+            Token::LTEQ => "LTEQ".to_string(),
+            Token::GTEQ => "GTEQ".to_string(),
             Token::BANG => "BANG".to_string(),
+            Token::DOTDOT => "DOTDOT".to_string(),
+            Token::DOTDOTEQ => "DOTDOTEQ".to_string(),
+            Token::AND => "AND".to_string(),
+            Token::OR => "OR".to_string(),
             Token::COMMA => "CAMMA".to_string(),
             Token::COLON => "COLON".to_string(),
             Token::SEMICOLON => "SEMICOLON".to_string(),
@@ -79,6 +121,11 @@ impl fmt::Display for Token {
             Token::NEQ => "NEQ".to_string(),
             Token::FOR => "FOR".to_string(),
             Token::IN => "IN".to_string(),
+            Token::WHILE => "WHILE".to_string(),
+            Token::BREAK => "BREAK".to_string(),
+            Token::CONTINUE => "CONTINUE".to_string(),
+            Token::NULL => "NULL".to_string(),
+            Token::DELETE => "DELETE".to_string(),
         };
         write!(f, "{}", value)
     }